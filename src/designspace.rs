@@ -0,0 +1,581 @@
+use std::{
+    collections::{BTreeMap, HashSet},
+    path::{Path, PathBuf},
+};
+
+use norad::Name;
+
+use crate::errors::{LoadDesignspaceDataError, LoadDesignspaceError};
+use crate::structs::SourceRecord;
+
+/// A location in the design space, mapping axis names to design coordinates.
+pub type Location = BTreeMap<String, f32>;
+
+/// The design space data of a Fontgarden, i.e. everything needed to write a
+/// designspace file next to exported UFOs.
+///
+/// Stored as tables at the root of the Fontgarden, with one column per axis
+/// for locations. Source locations live in the source registry, see
+/// [`SourceRecord`].
+#[derive(Debug, Default, PartialEq)]
+pub struct Designspace {
+    pub axes: Vec<Axis>,
+    /// Sparse layer sources, by their designspace source name.
+    pub layer_sources: BTreeMap<Name, LayerSource>,
+    pub instances: Vec<Instance>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Axis {
+    pub name: String,
+    pub tag: String,
+    pub minimum: Option<f32>,
+    pub default: f32,
+    pub maximum: Option<f32>,
+    pub hidden: bool,
+    /// Mapping of user to design coordinates.
+    pub map: Vec<(f32, f32)>,
+}
+
+/// A layer of a source that is a (sparse) source in its own right.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LayerSource {
+    pub source: Name,
+    pub layer: Name,
+    pub location: Location,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Instance {
+    pub name: Option<String>,
+    pub familyname: Option<String>,
+    pub stylename: Option<String>,
+    pub filename: Option<String>,
+    pub postscriptfontname: Option<String>,
+    pub stylemapfamilyname: Option<String>,
+    pub stylemapstylename: Option<String>,
+    pub location: Location,
+}
+
+impl Designspace {
+    /// Take the axes, layer sources and instances from a designspace
+    /// document. Sources are named like [`crate::util::designspace_ufos`] does.
+    ///
+    /// Rules and libs are not kept, see
+    /// [`crate::util::unkept_designspace_elements`].
+    pub fn from_designspace_document(
+        document: &norad::designspace::DesignSpaceDocument,
+    ) -> Result<Self, LoadDesignspaceError> {
+        let axes = document.axes.iter().map(Axis::from_norad).collect();
+
+        let ufos: BTreeMap<PathBuf, Name> = crate::util::designspace_ufos(document, Path::new(""))?
+            .into_iter()
+            .collect();
+
+        let mut layer_sources = BTreeMap::new();
+        for source in &document.sources {
+            if let Some(layer) = &source.layer {
+                let source_name = crate::util::designspace_source_name(source)?;
+                let layer = Name::new(layer)
+                    .map_err(|e| LoadDesignspaceError::NamingError(layer.clone(), e))?;
+                let ufo_source_name = ufos[&PathBuf::from(&source.filename)].clone();
+                layer_sources.insert(
+                    source_name,
+                    LayerSource {
+                        source: ufo_source_name,
+                        layer,
+                        location: location_from_norad(&source.location),
+                    },
+                );
+            }
+        }
+
+        let instances = document
+            .instances
+            .iter()
+            .map(|instance| Instance {
+                name: instance.name.clone(),
+                familyname: instance.familyname.clone(),
+                stylename: instance.stylename.clone(),
+                filename: instance.filename.clone(),
+                postscriptfontname: instance.postscriptfontname.clone(),
+                stylemapfamilyname: instance.stylemapfamilyname.clone(),
+                stylemapstylename: instance.stylemapstylename.clone(),
+                location: location_from_norad(&instance.location),
+            })
+            .collect();
+
+        Ok(Self {
+            axes,
+            layer_sources,
+            instances,
+        })
+    }
+
+    /// Merge the design space of another designspace document into this one,
+    /// e.g. of an italic designspace next to the upright one.
+    ///
+    /// Axes of the same name must agree and layer sources of the same name
+    /// must be layers of the same source. Instances with the same family and
+    /// style name as a stored one replace it, others are added.
+    pub fn merge(&mut self, other: Designspace) -> Result<(), LoadDesignspaceError> {
+        for axis in &other.axes {
+            if let Some(stored) = self.axes.iter().find(|a| a.name == axis.name) {
+                if stored != axis {
+                    return Err(LoadDesignspaceError::AxisMismatch(axis.name.clone()));
+                }
+            }
+        }
+        for (name, layer_source) in &other.layer_sources {
+            if let Some(stored) = self.layer_sources.get(name) {
+                if stored.source != layer_source.source {
+                    return Err(LoadDesignspaceError::LayerSourceConflict(
+                        name.clone(),
+                        stored.source.clone(),
+                        layer_source.source.clone(),
+                    ));
+                }
+            }
+        }
+
+        for axis in other.axes {
+            if !self.axes.iter().any(|a| a.name == axis.name) {
+                self.axes.push(axis);
+            }
+        }
+        self.layer_sources.extend(other.layer_sources);
+        for instance in other.instances {
+            let stored = self.instances.iter_mut().find(|stored| {
+                stored.familyname == instance.familyname && stored.stylename == instance.stylename
+            });
+            match stored {
+                Some(stored) => *stored = instance,
+                None => self.instances.push(instance),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Make a designspace document referencing the UFOs of `source_names`,
+    /// as exported into files named after the filename stem of each source
+    /// in `registry`.
+    ///
+    /// Instances outside of the part of the design space spanned by the
+    /// sources are left out, as they cannot be built from them.
+    pub fn to_designspace_document(
+        &self,
+        registry: &BTreeMap<Name, SourceRecord>,
+        source_names: &HashSet<Name>,
+        font_info: &BTreeMap<Name, norad::FontInfo>,
+    ) -> norad::designspace::DesignSpaceDocument {
+        let mut source_names: Vec<&Name> = source_names.iter().collect();
+        source_names.sort();
+
+        let mut sources = Vec::new();
+        for source_name in source_names {
+            let info = font_info.get(source_name);
+            let record = registry.get(source_name);
+            let filename = match record {
+                Some(record) => format!("{}.ufo", record.filename),
+                None => format!("{source_name}.ufo"),
+            };
+            sources.push(norad::designspace::Source {
+                familyname: info.and_then(|info| info.family_name.clone()),
+                stylename: info.and_then(|info| info.style_name.clone()),
+                name: source_name.to_string(),
+                filename: filename.clone(),
+                location: record
+                    .map(|record| self.location_to_norad(&record.location))
+                    .unwrap_or_default(),
+                ..Default::default()
+            });
+            for (name, layer_source) in self
+                .layer_sources
+                .iter()
+                .filter(|(_, layer_source)| layer_source.source == *source_name)
+            {
+                sources.push(norad::designspace::Source {
+                    name: name.to_string(),
+                    filename: filename.clone(),
+                    layer: Some(layer_source.layer.to_string()),
+                    location: self.location_to_norad(&layer_source.location),
+                    ..Default::default()
+                });
+            }
+        }
+
+        // The extent of the sources on each axis, in design coordinates.
+        let mut extents: BTreeMap<&str, (f32, f32)> = BTreeMap::new();
+        for source in &sources {
+            for axis in &self.axes {
+                let value = source
+                    .location
+                    .iter()
+                    .find(|dimension| dimension.name == axis.name)
+                    .and_then(|dimension| dimension.xvalue)
+                    .unwrap_or_else(|| axis.design_default());
+                let extent = extents.entry(&axis.name).or_insert((value, value));
+                extent.0 = extent.0.min(value);
+                extent.1 = extent.1.max(value);
+            }
+        }
+
+        let instances = self
+            .instances
+            .iter()
+            .filter(|instance| {
+                self.axes.iter().all(|axis| {
+                    let value = instance
+                        .location
+                        .get(&axis.name)
+                        .copied()
+                        .unwrap_or_else(|| axis.design_default());
+                    extents
+                        .get(axis.name.as_str())
+                        .is_some_and(|(min, max)| (*min..=*max).contains(&value))
+                })
+            })
+            .map(|instance| norad::designspace::Instance {
+                name: instance.name.clone(),
+                familyname: instance.familyname.clone(),
+                stylename: instance.stylename.clone(),
+                filename: instance.filename.clone(),
+                postscriptfontname: instance.postscriptfontname.clone(),
+                stylemapfamilyname: instance.stylemapfamilyname.clone(),
+                stylemapstylename: instance.stylemapstylename.clone(),
+                location: self.location_to_norad(&instance.location),
+                ..Default::default()
+            })
+            .collect();
+
+        norad::designspace::DesignSpaceDocument {
+            format: 4.1,
+            axes: self.axes.iter().map(Axis::to_norad).collect(),
+            sources,
+            instances,
+            ..Default::default()
+        }
+    }
+
+    /// Convert a location to norad dimensions, in axis order.
+    fn location_to_norad(&self, location: &Location) -> Vec<norad::designspace::Dimension> {
+        self.axes
+            .iter()
+            .filter_map(|axis| {
+                location
+                    .get(&axis.name)
+                    .map(|value| norad::designspace::Dimension {
+                        name: axis.name.clone(),
+                        xvalue: Some(*value),
+                        ..Default::default()
+                    })
+            })
+            .collect()
+    }
+
+    pub(crate) fn from_path(path: &Path) -> Result<Self, LoadDesignspaceDataError> {
+        let mut designspace = Self::default();
+
+        let axes_path = path.join("axes.csv");
+        if axes_path.exists() {
+            designspace.axes = Self::load_axes(&axes_path)?;
+        }
+
+        let layer_sources_path = path.join("layer_sources.csv");
+        if layer_sources_path.exists() {
+            for (record, location) in load_location_table(&layer_sources_path, 3)? {
+                let mut record = record.into_iter().map(parse_name);
+                let (name, source, layer) = (
+                    record.next().unwrap()?,
+                    record.next().unwrap()?,
+                    record.next().unwrap()?,
+                );
+                designspace.layer_sources.insert(
+                    name,
+                    LayerSource {
+                        source,
+                        layer,
+                        location,
+                    },
+                );
+            }
+        }
+
+        let instances_path = path.join("instances.csv");
+        if instances_path.exists() {
+            for (record, location) in load_location_table(&instances_path, 7)? {
+                let mut record = record
+                    .into_iter()
+                    .map(|v| if v.is_empty() { None } else { Some(v) });
+                designspace.instances.push(Instance {
+                    name: record.next().flatten(),
+                    familyname: record.next().flatten(),
+                    stylename: record.next().flatten(),
+                    filename: record.next().flatten(),
+                    postscriptfontname: record.next().flatten(),
+                    stylemapfamilyname: record.next().flatten(),
+                    stylemapstylename: record.next().flatten(),
+                    location,
+                });
+            }
+        }
+
+        Ok(designspace)
+    }
+
+    pub(crate) fn save(&self, path: &Path) -> Result<(), csv::Error> {
+        if !self.axes.is_empty() {
+            self.write_axes(&path.join("axes.csv"))?;
+        }
+
+        if !self.layer_sources.is_empty() {
+            let rows = self.layer_sources.iter().map(|(name, layer_source)| {
+                (
+                    vec![
+                        name.to_string(),
+                        layer_source.source.to_string(),
+                        layer_source.layer.to_string(),
+                    ],
+                    &layer_source.location,
+                )
+            });
+            self.write_location_table(
+                &path.join("layer_sources.csv"),
+                &["name", "source", "layer"],
+                rows,
+            )?;
+        }
+
+        if !self.instances.is_empty() {
+            let rows = self.instances.iter().map(|instance| {
+                let fields = [
+                    &instance.name,
+                    &instance.familyname,
+                    &instance.stylename,
+                    &instance.filename,
+                    &instance.postscriptfontname,
+                    &instance.stylemapfamilyname,
+                    &instance.stylemapstylename,
+                ];
+                (
+                    fields
+                        .into_iter()
+                        .map(|field| field.clone().unwrap_or_default())
+                        .collect(),
+                    &instance.location,
+                )
+            });
+            self.write_location_table(
+                &path.join("instances.csv"),
+                &[
+                    "name",
+                    "familyname",
+                    "stylename",
+                    "filename",
+                    "postscriptfontname",
+                    "stylemapfamilyname",
+                    "stylemapstylename",
+                ],
+                rows,
+            )?;
+        }
+
+        Ok(())
+    }
+
+    fn load_axes(path: &Path) -> Result<Vec<Axis>, LoadDesignspaceDataError> {
+        let mut axes = Vec::new();
+        let mut reader = csv::Reader::from_path(path).map_err(LoadDesignspaceDataError::Csv)?;
+
+        type Record = (String, String, Option<f32>, f32, Option<f32>, bool, String);
+        for result in reader.deserialize() {
+            let record: Record = result.map_err(LoadDesignspaceDataError::Csv)?;
+
+            let mut map = Vec::new();
+            for mapping in record.6.split_whitespace() {
+                let parsed = mapping
+                    .split_once(':')
+                    .and_then(|(input, output)| Some((input.parse().ok()?, output.parse().ok()?)));
+                match parsed {
+                    Some(mapping) => map.push(mapping),
+                    None => {
+                        return Err(LoadDesignspaceDataError::InvalidAxisMap(
+                            record.0,
+                            mapping.into(),
+                        ))
+                    }
+                }
+            }
+
+            axes.push(Axis {
+                name: record.0,
+                tag: record.1,
+                minimum: record.2,
+                default: record.3,
+                maximum: record.4,
+                hidden: record.5,
+                map,
+            });
+        }
+
+        Ok(axes)
+    }
+
+    fn write_axes(&self, path: &Path) -> Result<(), csv::Error> {
+        let mut writer = csv::Writer::from_path(path)?;
+
+        writer.write_record([
+            "name", "tag", "minimum", "default", "maximum", "hidden", "map",
+        ])?;
+        for axis in &self.axes {
+            let map: Vec<String> = axis
+                .map
+                .iter()
+                .map(|(input, output)| format!("{input}:{output}"))
+                .collect();
+            writer.serialize((
+                &axis.name,
+                &axis.tag,
+                axis.minimum,
+                axis.default,
+                axis.maximum,
+                axis.hidden,
+                map.join(" "),
+            ))?;
+        }
+        writer.flush()?;
+
+        Ok(())
+    }
+
+    /// Write a table with the given leading columns, followed by one column
+    /// per axis for the location.
+    pub(crate) fn write_location_table<'a>(
+        &self,
+        path: &Path,
+        columns: &[&str],
+        rows: impl Iterator<Item = (Vec<String>, &'a Location)>,
+    ) -> Result<(), csv::Error> {
+        let mut writer = csv::Writer::from_path(path)?;
+
+        let mut headers = columns.to_vec();
+        headers.extend(self.axes.iter().map(|axis| axis.name.as_str()));
+        writer.write_record(&headers)?;
+
+        for (mut record, location) in rows {
+            for axis in &self.axes {
+                let value = location.get(&axis.name);
+                record.push(value.map(|v| v.to_string()).unwrap_or_default());
+            }
+            writer.write_record(&record)?;
+        }
+        writer.flush()?;
+
+        Ok(())
+    }
+}
+
+impl Axis {
+    /// The default of the axis in design coordinates.
+    fn design_default(&self) -> f32 {
+        let mut map = self.map.clone();
+        map.sort_by(|a, b| a.0.total_cmp(&b.0));
+        match map.iter().position(|(input, _)| *input >= self.default) {
+            None => map.last().map_or(self.default, |(_, output)| *output),
+            Some(0) => map[0].1,
+            Some(i) => {
+                let ((in0, out0), (in1, out1)) = (map[i - 1], map[i]);
+                out0 + (out1 - out0) * (self.default - in0) / (in1 - in0)
+            }
+        }
+    }
+
+    fn from_norad(axis: &norad::designspace::Axis) -> Self {
+        Self {
+            name: axis.name.clone(),
+            tag: axis.tag.clone(),
+            minimum: axis.minimum,
+            default: axis.default,
+            maximum: axis.maximum,
+            hidden: axis.hidden,
+            map: axis
+                .map
+                .iter()
+                .flatten()
+                .map(|mapping| (mapping.input, mapping.output))
+                .collect(),
+        }
+    }
+
+    fn to_norad(&self) -> norad::designspace::Axis {
+        let map = self
+            .map
+            .iter()
+            .map(|(input, output)| norad::designspace::AxisMapping {
+                input: *input,
+                output: *output,
+            })
+            .collect::<Vec<_>>();
+        norad::designspace::Axis {
+            name: self.name.clone(),
+            tag: self.tag.clone(),
+            minimum: self.minimum,
+            default: self.default,
+            maximum: self.maximum,
+            hidden: self.hidden,
+            map: if map.is_empty() { None } else { Some(map) },
+            ..Default::default()
+        }
+    }
+}
+
+pub(crate) fn location_from_norad(dimensions: &[norad::designspace::Dimension]) -> Location {
+    dimensions
+        .iter()
+        .filter_map(|dimension| Some((dimension.name.clone(), dimension.xvalue?)))
+        .collect()
+}
+
+pub(crate) fn parse_name(name: String) -> Result<Name, LoadDesignspaceDataError> {
+    Name::new(&name).map_err(|e| LoadDesignspaceDataError::NamingError(name.clone(), e))
+}
+
+/// Load a table with `columns` leading columns, followed by one column per
+/// axis for the location.
+pub(crate) fn load_location_table(
+    path: &Path,
+    columns: usize,
+) -> Result<Vec<(Vec<String>, Location)>, LoadDesignspaceDataError> {
+    let mut rows = Vec::new();
+    let mut reader = csv::Reader::from_path(path).map_err(LoadDesignspaceDataError::Csv)?;
+
+    let axis_names: Vec<String> = reader
+        .headers()
+        .map_err(LoadDesignspaceDataError::Csv)?
+        .iter()
+        .skip(columns)
+        .map(String::from)
+        .collect();
+
+    for result in reader.records() {
+        let record = result.map_err(LoadDesignspaceDataError::Csv)?;
+
+        let mut location = Location::new();
+        for (axis_name, value) in axis_names.iter().zip(record.iter().skip(columns)) {
+            if value.is_empty() {
+                continue;
+            }
+            let value = value.parse().map_err(|e| {
+                LoadDesignspaceDataError::InvalidCoordinate(axis_name.clone(), value.into(), e)
+            })?;
+            location.insert(axis_name.clone(), value);
+        }
+
+        let mut fields: Vec<String> = record.iter().take(columns).map(String::from).collect();
+        fields.resize(columns, String::new());
+        rows.push((fields, location));
+    }
+
+    Ok(rows)
+}
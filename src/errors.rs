@@ -1,4 +1,7 @@
-use std::{collections::HashSet, path::PathBuf};
+use std::{
+    collections::{BTreeMap, BTreeSet, HashSet},
+    path::PathBuf,
+};
 
 use norad::Name;
 use thiserror::Error;
@@ -17,6 +20,76 @@ pub enum LoadError {
     NamingError(String, #[source] norad::error::NamingError),
     #[error("failed to load set '{0}'")]
     LoadSet(Name, #[source] LoadSetError),
+    #[error("failed to load the font_info.csv file")]
+    LoadFontInfo(#[source] LoadFontInfoError),
+    #[error("failed to load the design space data")]
+    LoadDesignspace(#[source] LoadDesignspaceDataError),
+    #[error("failed to load the sources.csv file")]
+    LoadSources(#[source] LoadDesignspaceDataError),
+    #[error("set '{0}' contains source '{1}', which is missing from sources.csv")]
+    UnknownSource(Name, Name),
+    #[error("failed to load the layer_order.csv file")]
+    LoadLayerOrder(#[source] csv::Error),
+    #[error("layer_order.csv lists source '{0}', which is missing from sources.csv")]
+    UnknownLayerOrderSource(Name),
+    #[error("failed to load the group_order.csv file")]
+    LoadGroupOrder(#[source] csv::Error),
+    #[error("failed to load the glyph_order.csv file")]
+    LoadGlyphOrder(#[source] csv::Error),
+    #[error(transparent)]
+    DefaultLayerMismatch(#[from] DefaultLayerMismatchError),
+    #[error("source '{1}' in set '{0}' references image '{2}', which is missing from the images directory")]
+    MissingImage(Name, Name, String),
+    #[error("failed to load the lib.plist file")]
+    LoadLib(#[source] plist::Error),
+    #[error("failed to load the lib and data of source '{0}'")]
+    LoadSourceData(String, #[source] LoadSourceDataError),
+    #[error("failed to load the components.csv file of set '{0}'")]
+    LoadComponents(Name, #[source] csv::Error),
+    #[error("components.csv of set '{0}' does not match the glyphs for glyph '{1}' and component '{2}'; delete the file to have it regenerated on the next save")]
+    ComponentsMismatch(Name, Name, Name),
+}
+
+/// The default layer of a source in a set differs from the one of the source
+/// in the Fontgarden, by set, source, found and expected layer name.
+#[derive(Error, Debug)]
+#[error("source '{1}' in set '{0}' has '{2}' as its default layer instead of '{3}'")]
+pub struct DefaultLayerMismatchError(pub Name, pub Name, pub Name, pub Name);
+
+#[derive(Error, Debug)]
+pub enum LoadSourceDataError {
+    #[error("failed to load data from disk")]
+    Io(#[from] std::io::Error),
+    #[error("failed to load the source's lib.plist file")]
+    LoadLib(#[source] plist::Error),
+}
+
+#[derive(Error, Debug)]
+pub enum LoadDesignspaceDataError {
+    #[error("failed to load data from disk")]
+    Csv(#[source] csv::Error),
+    #[error("invalid name '{0}'")]
+    NamingError(String, #[source] norad::error::NamingError),
+    #[error("invalid coordinate '{1}' for axis {0}")]
+    InvalidCoordinate(String, String, #[source] std::num::ParseFloatError),
+    #[error("invalid mapping '{1}' for axis {0}, must be 'input:output'")]
+    InvalidAxisMap(String, String),
+    #[error("invalid italic flag '{1}' for source {0}, must be 'true' or 'false'")]
+    InvalidItalicFlag(Name, String),
+}
+
+#[derive(Error, Debug)]
+pub enum LoadFontInfoError {
+    #[error("failed to load data from disk")]
+    Csv(#[source] csv::Error),
+    #[error("invalid source name '{0}'")]
+    InvalidSourceName(String, #[source] norad::error::NamingError),
+    #[error("invalid value type '{1}' for field {0}")]
+    InvalidValueKind(String, String, #[source] anyhow::Error),
+    #[error("invalid value '{2}' for field {0} in source {1}")]
+    InvalidValue(String, Name, String, #[source] anyhow::Error),
+    #[error("failed to convert the font info of source {0}")]
+    Convert(Name, #[source] plist::Error),
 }
 
 #[derive(Error, Debug)]
@@ -27,10 +100,44 @@ pub enum LoadSetError {
     NamingError(String, #[source] norad::error::NamingError),
     #[error("failed to load the set's glyph_data.csv file")]
     LoadGlyphData(#[source] LoadGlyphDataError),
+    #[error("failed to load the set's groups.csv file")]
+    LoadGroups(#[source] csv::Error),
+    #[error("failed to load the set's lib.plist file")]
+    LoadLib(#[source] plist::Error),
+    #[error("failed to load the set's metrics.csv file")]
+    LoadMetrics(#[source] LoadMetricsError),
+    #[error("failed to load the set's anchors.csv file")]
+    LoadAnchors(#[source] LoadAnchorsError),
+    #[error("failed to load shared glyph from '{0}'")]
+    LoadSharedGlyph(PathBuf, #[source] norad::error::GlifLoadError),
     #[error("failed to load source '{0}'")]
     LoadSource(Name, #[source] LoadSourceError),
 }
 
+#[derive(Error, Debug)]
+pub enum LoadMetricsError {
+    #[error("failed to load data from disk")]
+    Csv(#[source] csv::Error),
+    #[error("invalid columns '{0}', must be 'width.<source>,height.<source>'")]
+    InvalidColumns(String),
+    #[error("invalid source name '{0}'")]
+    InvalidSourceName(String, #[source] norad::error::NamingError),
+    #[error("invalid glyph name '{0}'")]
+    InvalidGlyphName(String, #[source] norad::error::NamingError),
+    #[error("invalid value '{1}' for glyph {0}")]
+    InvalidValue(Name, String, #[source] std::num::ParseFloatError),
+    #[error("the set has no source '{0}'")]
+    UnknownSource(Name),
+}
+
+#[derive(Error, Debug)]
+pub enum LoadAnchorsError {
+    #[error("failed to load data from disk")]
+    Csv(#[source] csv::Error),
+    #[error("there is no glyph '{0}' in layer '{2}' of source '{1}'")]
+    UnknownGlyph(Name, Name, Name),
+}
+
 #[derive(Error, Debug)]
 pub enum LoadGlyphDataError {
     #[error("failed to load data from disk")]
@@ -51,6 +158,12 @@ pub enum LoadSourceError {
     NoDefaultLayer,
     #[error("failed to load layer from '{0}'")]
     LoadLayer(PathBuf, #[source] LoadLayerError),
+    #[error("failed to load the source's kerning.csv file")]
+    LoadKerning(#[source] csv::Error),
+    #[error("failed to load the source's images.csv file")]
+    LoadImages(#[source] csv::Error),
+    #[error("failed to load the source's lib.plist file")]
+    LoadLib(#[source] plist::Error),
 }
 
 #[derive(Error, Debug)]
@@ -61,8 +174,16 @@ pub enum LoadLayerError {
     LoadLayerInfo(#[source] plist::Error),
     #[error("failed to load the layer's color_marks.csv file")]
     LoadColorMarks(#[source] csv::Error),
+    #[error("failed to load the layer's encoded_glyphs.csv file")]
+    LoadEncodedGlyphs(#[source] csv::Error),
     #[error("failed to load glyph from '{0}'")]
     LoadGlyph(PathBuf, #[source] norad::error::GlifLoadError),
+    #[error("failed to load the layer's shared_glyphs.csv file")]
+    LoadSharedGlyphs(#[source] csv::Error),
+    #[error("there is no shared glyph '{1}' for glyph '{0}'")]
+    UnknownSharedGlyph(Name, String),
+    #[error("shared glyph '{1}' for glyph '{0}' is named '{2}'")]
+    SharedGlyphNameMismatch(Name, String, Name),
 }
 
 #[derive(Error, Debug)]
@@ -71,16 +192,74 @@ pub enum SaveError {
     Cleanup(#[source] std::io::Error),
     #[error("failed to create target fontgarden directory")]
     CreateDir(#[source] std::io::Error),
+    #[error("failed to write the features.fea file")]
+    WriteFeatures(#[source] std::io::Error),
+    #[error("failed to write the font_info.csv file")]
+    WriteFontInfo(#[source] WriteFontInfoError),
+    #[error("failed to write the design space data")]
+    WriteDesignspace(#[source] csv::Error),
+    #[error("failed to write the sources.csv file")]
+    WriteSources(#[source] csv::Error),
+    #[error("failed to write the layer_order.csv file")]
+    WriteLayerOrder(#[source] csv::Error),
+    #[error("failed to write the group_order.csv file")]
+    WriteGroupOrder(#[source] csv::Error),
+    #[error("failed to write the glyph_order.csv file")]
+    WriteGlyphOrder(#[source] csv::Error),
+    #[error("failed to create the images directory")]
+    CreateImagesDir(#[source] std::io::Error),
+    #[error("failed to write image '{0}'")]
+    WriteImage(String, #[source] std::io::Error),
+    #[error("failed to write the lib.plist file")]
+    WriteLib(#[source] plist::Error),
+    #[error("failed to save the lib and data of source '{0}'")]
+    SaveSourceData(Name, #[source] SaveSourceDataError),
+    #[error("failed to write the components.csv file of set '{0}'")]
+    WriteComponents(Name, #[source] csv::Error),
     #[error("failed to save set '{0}'")]
     SaveSet(Name, #[source] SaveSetError),
 }
 
+#[derive(Error, Debug)]
+pub enum WriteFontInfoError {
+    #[error("failed to write data to disk")]
+    Csv(#[source] csv::Error),
+    #[error("failed to convert the font info of source {0}")]
+    Convert(Name, #[source] plist::Error),
+}
+
+#[derive(Error, Debug)]
+pub enum SaveSourceDataError {
+    #[error("failed to create the source directory")]
+    CreateDir(#[source] std::io::Error),
+    #[error("failed to write the source's lib.plist file")]
+    WriteLib(#[source] plist::Error),
+    #[error("failed to write data file '{0}'")]
+    WriteData(PathBuf, #[source] std::io::Error),
+}
+
 #[derive(Error, Debug)]
 pub enum SaveSetError {
     #[error("failed to create set directory")]
     CreateDir(#[source] std::io::Error),
     #[error("failed to write the set's glyph_data.csv file")]
     WriteGlyphData(#[source] csv::Error),
+    #[error("failed to write the set's groups.csv file")]
+    WriteGroups(#[source] csv::Error),
+    #[error("failed to write the set's lib.plist file")]
+    WriteLib(#[source] plist::Error),
+    #[error("failed to write the set's metrics.csv file")]
+    WriteMetrics(#[source] csv::Error),
+    #[error("failed to write the set's anchors.csv file")]
+    WriteAnchors(#[source] csv::Error),
+    #[error("failed to write the set's features.fea file")]
+    WriteFeatures(#[source] std::io::Error),
+    #[error("failed to encode glyph '{0}'")]
+    EncodeGlyph(Name, #[source] norad::error::GlifWriteError),
+    #[error("failed to create the set's shared_glyphs directory")]
+    CreateSharedGlyphsDir(#[source] std::io::Error),
+    #[error("failed to write shared glyph '{0}'")]
+    WriteSharedGlyph(String, #[source] std::io::Error),
     #[error("failed to save source '{0}'")]
     SaveSource(Name, #[source] SaveSourceError),
 }
@@ -91,6 +270,12 @@ pub enum SaveSourceError {
     CreateDir(#[source] std::io::Error),
     #[error("failed to save layer '{0}'")]
     SaveLayer(Name, #[source] SaveLayerError),
+    #[error("failed to write the source's kerning.csv file")]
+    WriteKerning(#[source] csv::Error),
+    #[error("failed to write the source's images.csv file")]
+    WriteImages(#[source] csv::Error),
+    #[error("failed to write the source's lib.plist file")]
+    WriteLib(#[source] plist::Error),
 }
 
 #[derive(Error, Debug)]
@@ -101,8 +286,125 @@ pub enum SaveLayerError {
     WriteLayerInfo(#[source] plist::Error),
     #[error("failed to write the layer's color_marks.csv file")]
     WriteColorMarks(#[source] csv::Error),
-    #[error("failed to save glyph '{0}'")]
-    SaveGlyph(Name, #[source] norad::error::GlifWriteError),
+    #[error("failed to write the layer's encoded_glyphs.csv file")]
+    WriteEncodedGlyphs(#[source] csv::Error),
+    #[error("failed to write glyph '{0}'")]
+    WriteGlyph(Name, #[source] std::io::Error),
+    #[error("failed to write the layer's shared_glyphs.csv file")]
+    WriteSharedGlyphs(#[source] csv::Error),
+}
+
+#[derive(Error, Debug)]
+pub enum LoadRenameMappingError {
+    #[error("failed to read the mapping file")]
+    Csv(#[from] csv::Error),
+    #[error("the mapping file must have the columns 'old' and 'new', not '{0}'")]
+    InvalidHeaders(String),
+    #[error("glyph '{0}' is renamed more than once")]
+    DuplicateOldName(Name),
+}
+
+#[derive(Error, Debug)]
+pub enum LoadSourceMappingError {
+    #[error("failed to read the mapping file")]
+    Csv(#[from] csv::Error),
+    #[error("'{0}' is mapped more than once")]
+    DuplicateEntry(String),
+    #[error("more than one entry maps to the source name '{0}'")]
+    DuplicateSourceName(Name),
+}
+
+#[derive(Error, Debug)]
+pub enum LoadDesignspaceError {
+    #[error("invalid source name '{0}'")]
+    NamingError(String, #[source] norad::error::NamingError),
+    #[error("axis '{0}' differs from the stored one of that name")]
+    AxisMismatch(String),
+    #[error("layer source '{0}' is a layer of source '{1}', not of '{2}'")]
+    LayerSourceConflict(Name, Name, Name),
+}
+
+#[derive(Error, Debug)]
+pub enum ImportError {
+    #[error("source '{0}' has '{1}' as its default layer, but the UFO has a non-default layer of that name")]
+    DefaultLayerConflict(Name, Name),
+    #[error(transparent)]
+    DefaultLayerMismatch(#[from] DefaultLayerMismatchError),
+    #[error("the UFO's public.glyphOrder is not a list of glyph names")]
+    InvalidGlyphOrder,
+    #[error("failed to load image '{0}' from the UFO")]
+    LoadImage(PathBuf, #[source] norad::error::StoreError),
+    #[error("failed to load data file '{0}' from the UFO")]
+    LoadData(PathBuf, #[source] norad::error::StoreError),
+    #[error("glyphs used as components differ from the ones stored for source '{0}': {}", .1.iter().map(|n| n.as_str()).collect::<Vec<_>>().join(", "))]
+    DivergingComponents(Name, Vec<Name>),
+    #[error("glyphs belong to sets other than the ones imported into: {}", .0.iter().map(|n| n.as_str()).collect::<Vec<_>>().join(", "))]
+    GlyphsInOtherSets(Vec<Name>),
+}
+
+#[derive(Error, Debug)]
+pub enum MoveError {
+    #[error("glyphs are not in any set: {}", .0.iter().map(|n| n.as_str()).collect::<Vec<_>>().join(", "))]
+    UnknownGlyphs(Vec<Name>),
+}
+
+#[derive(Error, Debug)]
+pub enum RenameError {
+    #[error("glyph '{0}' is not in any set")]
+    UnknownGlyph(Name),
+    #[error("cannot rename glyph '{0}' to '{1}', the name is already taken")]
+    NameCollision(Name, Name),
+}
+
+#[derive(Error, Debug)]
+pub enum RemoveError {
+    #[error("glyphs are not in any set: {}", .0.iter().map(|n| n.as_str()).collect::<Vec<_>>().join(", "))]
+    UnknownGlyphs(Vec<Name>),
+    #[error("glyphs are used as components by other glyphs: {}", format_dependents(.0))]
+    UsedAsComponents(BTreeMap<Name, BTreeSet<Name>>),
+}
+
+fn format_dependents(dependents: &BTreeMap<Name, BTreeSet<Name>>) -> String {
+    dependents
+        .iter()
+        .map(|(dependent, used)| {
+            let used: Vec<&str> = used.iter().map(|n| n.as_str()).collect();
+            format!("{dependent} uses {}", used.join(", "))
+        })
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+#[derive(Error, Debug)]
+pub enum SetError {
+    #[error("there is no set '{0}'")]
+    UnknownSet(Name),
+    #[error("there already is a set '{0}'")]
+    SetExists(Name),
+    #[error("cannot merge set '{0}' into itself")]
+    SameSet(Name),
+    #[error("set '{0}' still has {1} glyphs; reassign them to another set")]
+    NotEmpty(Name, usize),
+    #[error("set '{0}' still has feature code or lib entries; merge it into another set instead")]
+    HasFeaturesOrLib(Name),
+    #[error("glyphs are not in set '{0}': {}", .1.iter().map(|n| n.as_str()).collect::<Vec<_>>().join(", "))]
+    GlyphsNotInSet(Name, Vec<Name>),
+}
+
+#[derive(Error, Debug)]
+pub enum SourceError {
+    #[error("there is no source '{0}'")]
+    UnknownSource(Name),
+    #[error("there already is a source '{0}'")]
+    SourceExists(Name),
+    #[error("failed to import the glyphs of the new source")]
+    Import(#[from] ImportError),
+    #[error("there is no axis '{0}' in the design space")]
+    UnknownAxis(String),
+    #[error("source '{0}' needs a location different from source '{1}'")]
+    SameLocation(Name, Name),
+    #[error("there already is a layer source '{0}'")]
+    LayerSourceExists(Name),
 }
 
 #[derive(Error, Debug)]
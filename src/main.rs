@@ -1,13 +1,14 @@
 use std::{
-    collections::{HashMap, HashSet},
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
     path::{Path, PathBuf},
 };
 
 use anyhow::{Context, Result};
-use clap::{ArgGroup, CommandFactory, Parser, Subcommand};
+use clap::{ArgGroup, CommandFactory, Parser, Subcommand, ValueEnum};
 use norad::Name;
-use structs::Fontgarden;
+use structs::{DivergencePolicy, Fontgarden, ImportMode, ImportOptions};
 
+mod designspace;
 mod errors;
 mod structs;
 mod util;
@@ -37,14 +38,38 @@ enum Commands {
         #[arg(long = "set", value_name = "NAME")]
         sets: Vec<Name>,
 
-        /// Unified Font Object (UFO) to import from.
+        /// Unified Font Object (UFO) to import from. A designspace file
+        /// imports all UFOs it references, named after its sources.
         #[arg(value_name = "UFOS")]
         fonts: Vec<PathBuf>,
-        //
-        // TODO:
-        // /// Set the source name of the font to be imported.
-        // #[arg(long = "source-name", value_name = "SOURCE_NAME")]
-        // source_names: Vec<Name>,
+
+        /// Source name of each UFO, in the same order as the UFOs. Use
+        /// multiple times. Cannot be combined with designspace files.
+        #[arg(long = "source-name", value_name = "SOURCE_NAME")]
+        source_names: Vec<Name>,
+
+        /// CSV file mapping UFOs to source names, by UFO path or by family
+        /// and style name [default: the styleName of each UFO].
+        #[arg(long, value_name = "MAPPING_FILE")]
+        source_mapping: Option<PathBuf>,
+
+        /// What to do when a glyph pulled in as a component differs from the
+        /// one already in the Fontgarden.
+        #[arg(long, value_enum, default_value_t = Divergence::Refuse)]
+        divergence: Divergence,
+
+        /// Only touch glyphs in the sets given with --set. Fail if a glyph to
+        /// import belongs to another set, skip components that do.
+        #[arg(long)]
+        only_set: bool,
+
+        /// Only update glyphs that are already in the Fontgarden.
+        #[arg(long, conflicts_with = "add_new_only")]
+        update_existing: bool,
+
+        /// Only add glyphs that are not in the Fontgarden yet.
+        #[arg(long)]
+        add_new_only: bool,
     },
     #[command(group(
         ArgGroup::new("glyph_names")
@@ -71,6 +96,233 @@ enum Commands {
         #[arg(long)]
         output_dir: Option<PathBuf>,
     },
+    #[command(group(
+        ArgGroup::new("glyphs")
+            .required(true)
+            .multiple(true)
+            .args(["glyph_names", "glyphs_file"]),
+    ))]
+    Move {
+        /// Fontgarden package path to move glyphs in.
+        fontgarden_path: PathBuf,
+
+        /// Glyphs to move.
+        #[arg(value_name = "GLYPHS")]
+        glyph_names: Vec<Name>,
+
+        /// A text file of glyphs to move, one per line.
+        #[arg(long, value_name = "GLYPHS_FILE")]
+        glyphs_file: Option<PathBuf>,
+
+        /// Set to move the glyphs into. Created if it does not exist.
+        #[arg(long = "to", value_name = "NAME")]
+        set: Name,
+
+        /// Also move the glyphs used as components only by the moved glyphs.
+        #[arg(long)]
+        with_components: bool,
+    },
+    #[command(group(
+        ArgGroup::new("renames")
+            .required(true)
+            .args(["old_name", "mapping_file"]),
+    ))]
+    RenameGlyph {
+        /// Fontgarden package path to rename glyphs in.
+        fontgarden_path: PathBuf,
+
+        /// Glyph to rename.
+        #[arg(requires = "new_name")]
+        old_name: Option<Name>,
+
+        /// New name of the glyph.
+        new_name: Option<Name>,
+
+        /// Alternatively, a CSV file with the columns `old` and `new`.
+        #[arg(long, value_name = "MAPPING_FILE")]
+        mapping_file: Option<PathBuf>,
+    },
+    #[command(group(
+        ArgGroup::new("glyphs")
+            .required(true)
+            .multiple(true)
+            .args(["glyph_names", "glyphs_file"]),
+    ))]
+    Remove {
+        /// Fontgarden package path to remove glyphs from.
+        fontgarden_path: PathBuf,
+
+        /// Glyphs to remove.
+        #[arg(value_name = "GLYPHS")]
+        glyph_names: Vec<Name>,
+
+        /// A text file of glyphs to remove, one per line.
+        #[arg(long, value_name = "GLYPHS_FILE")]
+        glyphs_file: Option<PathBuf>,
+
+        /// Remove the glyphs even if other glyphs use them as components.
+        #[arg(long)]
+        force: bool,
+    },
+    /// Manage sets.
+    Set {
+        #[command(subcommand)]
+        command: SetCommands,
+    },
+    /// Manage sources.
+    Source {
+        #[command(subcommand)]
+        command: SourceCommands,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+enum SourceCommands {
+    /// Add a source, importing all glyphs already in the Fontgarden from a UFO.
+    Add {
+        /// Fontgarden package path.
+        fontgarden_path: PathBuf,
+
+        /// Name of the new source.
+        name: Name,
+
+        /// Unified Font Object (UFO) to import from.
+        #[arg(long = "from", value_name = "UFO")]
+        font: PathBuf,
+
+        /// Location of the source on an axis of the design space. Use once
+        /// per axis; axes left out are at their default.
+        #[arg(long = "location", value_name = "AXIS=VALUE", value_parser = parse_location_entry)]
+        location: Vec<(String, f32)>,
+    },
+    /// Rename a source in all sets.
+    Rename {
+        /// Fontgarden package path.
+        fontgarden_path: PathBuf,
+
+        /// Current name of the source.
+        old_name: Name,
+
+        /// New name of the source.
+        new_name: Name,
+    },
+    /// Remove a source from all sets.
+    Remove {
+        /// Fontgarden package path.
+        fontgarden_path: PathBuf,
+
+        /// Source to remove.
+        name: Name,
+    },
+    /// Copy a source under a new name in all sets.
+    Duplicate {
+        /// Fontgarden package path.
+        fontgarden_path: PathBuf,
+
+        /// Source to copy.
+        name: Name,
+
+        /// Name of the copy.
+        new_name: Name,
+
+        /// Location of the copy on an axis of the design space, if different
+        /// from the original. Use once per axis. Required if the design
+        /// space has axes.
+        #[arg(long = "location", value_name = "AXIS=VALUE", value_parser = parse_location_entry)]
+        location: Vec<(String, f32)>,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+enum SetCommands {
+    /// Create an empty set.
+    New {
+        /// Fontgarden package path.
+        fontgarden_path: PathBuf,
+
+        /// Name of the new set.
+        name: Name,
+    },
+    /// Rename a set.
+    Rename {
+        /// Fontgarden package path.
+        fontgarden_path: PathBuf,
+
+        /// Current name of the set.
+        old_name: Name,
+
+        /// New name of the set.
+        new_name: Name,
+    },
+    /// Merge a set into another one, including its feature code and lib.
+    Merge {
+        /// Fontgarden package path.
+        fontgarden_path: PathBuf,
+
+        /// Set to merge and remove.
+        name: Name,
+
+        /// Set to merge into.
+        #[arg(long = "into", value_name = "NAME")]
+        into: Name,
+    },
+    /// Move some glyphs of a set into a new set.
+    #[command(group(
+        ArgGroup::new("selection")
+            .required(true)
+            .args(["glyphs_file", "script"]),
+    ))]
+    Split {
+        /// Fontgarden package path.
+        fontgarden_path: PathBuf,
+
+        /// Set to split.
+        name: Name,
+
+        /// Name of the new set.
+        new_name: Name,
+
+        /// A text file of glyphs to move into the new set, one per line.
+        #[arg(long, value_name = "GLYPHS_FILE")]
+        glyphs_file: Option<PathBuf>,
+
+        /// Alternatively, a Unicode script like Cyrillic or Cyrl: move the
+        /// glyphs with a codepoint of this script into the new set.
+        #[arg(long)]
+        script: Option<String>,
+    },
+    /// Delete a set.
+    Delete {
+        /// Fontgarden package path.
+        fontgarden_path: PathBuf,
+
+        /// Set to delete.
+        name: Name,
+
+        /// Set to reassign the glyphs to. Required if the set has glyphs.
+        #[arg(long = "into", value_name = "NAME")]
+        into: Option<Name>,
+    },
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum Divergence {
+    /// Fail the import.
+    Refuse,
+    /// Import the incoming glyph over the stored one and report it.
+    Warn,
+    /// Keep the stored glyph and report it.
+    KeepStored,
+}
+
+impl From<Divergence> for DivergencePolicy {
+    fn from(divergence: Divergence) -> Self {
+        match divergence {
+            Divergence::Refuse => DivergencePolicy::Refuse,
+            Divergence::Warn => DivergencePolicy::Warn,
+            Divergence::KeepStored => DivergencePolicy::KeepStored,
+        }
+    }
 }
 
 fn main() -> Result<()> {
@@ -85,8 +337,34 @@ fn main() -> Result<()> {
             glyphs_files,
             sets,
             fonts,
+            source_names,
+            source_mapping,
+            divergence,
+            only_set,
+            update_existing,
+            add_new_only,
         } => {
-            import(glyphs_files, sets, fontgarden_path, fonts)?;
+            let mode = if *update_existing {
+                ImportMode::UpdateExisting
+            } else if *add_new_only {
+                ImportMode::AddNewOnly
+            } else {
+                ImportMode::All
+            };
+            let options = ImportOptions {
+                divergence: (*divergence).into(),
+                mode,
+                only_sets: only_set.then(|| sets.iter().cloned().collect()),
+            };
+            import(
+                glyphs_files,
+                sets,
+                fontgarden_path,
+                fonts,
+                source_names,
+                source_mapping.as_ref().map(|f| f.as_ref()),
+                &options,
+            )?;
         }
         Commands::Export {
             fontgarden_path,
@@ -103,6 +381,55 @@ fn main() -> Result<()> {
                 output_dir.as_ref(),
             )?;
         }
+        Commands::Move {
+            fontgarden_path,
+            glyph_names,
+            glyphs_file,
+            set,
+            with_components,
+        } => {
+            move_glyphs(
+                fontgarden_path,
+                glyph_names,
+                glyphs_file.as_ref().map(|f| f.as_ref()),
+                set,
+                *with_components,
+            )?;
+        }
+        Commands::RenameGlyph {
+            fontgarden_path,
+            old_name,
+            new_name,
+            mapping_file,
+        } => {
+            let renames = match (old_name, new_name, mapping_file) {
+                (Some(old), Some(new), _) => BTreeMap::from([(old.clone(), new.clone())]),
+                (_, _, Some(path)) => {
+                    util::load_rename_mapping(path).context("can't load mapping file")?
+                }
+                _ => unreachable!("clap requires a rename"),
+            };
+            rename_glyphs(fontgarden_path, &renames)?;
+        }
+        Commands::Remove {
+            fontgarden_path,
+            glyph_names,
+            glyphs_file,
+            force,
+        } => {
+            remove_glyphs(
+                fontgarden_path,
+                glyph_names,
+                glyphs_file.as_ref().map(|f| f.as_ref()),
+                *force,
+            )?;
+        }
+        Commands::Set { command } => {
+            manage_sets(command)?;
+        }
+        Commands::Source { command } => {
+            manage_sources(command)?;
+        }
     }
 
     Ok(())
@@ -120,6 +447,9 @@ fn import(
     sets: &[Name],
     fontgarden_path: &Path,
     fonts: &[PathBuf],
+    source_names: &[Name],
+    source_mapping: Option<&Path>,
+    options: &ImportOptions,
 ) -> Result<()> {
     if !glyphs_files.is_empty() && glyphs_files.len() != sets.len() {
         error_and_exit(
@@ -127,8 +457,46 @@ fn import(
             "The --glyphs-file argument must occur as often as the --set argument.",
         );
     }
-
-    let mut fontgarden = Fontgarden::from_path(fontgarden_path).context("can't load fontgarden")?;
+    let mut fontgarden = load_fontgarden(fontgarden_path)?;
+
+    // Expand designspace files into the UFOs they reference, which come with
+    // source names attached. Their axes, locations and instances are taken
+    // over once the sources are registered.
+    let mut ufos: Vec<(PathBuf, Option<(usize, Name)>)> = Vec::new();
+    let mut documents = Vec::new();
+    for path in fonts {
+        if path.extension().is_some_and(|e| e == "designspace") {
+            let document = norad::designspace::DesignSpaceDocument::load(path)
+                .with_context(|| format!("can't load designspace {}", path.display()))?;
+            let unkept = util::unkept_designspace_elements(path)
+                .with_context(|| format!("can't read designspace {}", path.display()))?;
+            if !unkept.is_empty() {
+                eprintln!(
+                    "Warning: not keeping the {} of {}.",
+                    unkept.join(" and "),
+                    path.display()
+                );
+            }
+            let base_dir = path.parent().unwrap_or_else(|| Path::new(""));
+            let sources = util::designspace_ufos(&document, base_dir)?;
+            let index = documents.len();
+            ufos.extend(sources.into_iter().map(|(p, n)| (p, Some((index, n)))));
+            documents.push((document, HashMap::new()));
+        } else {
+            ufos.push((path.clone(), None));
+        }
+    }
+    if !source_names.is_empty() && (source_names.len() != fonts.len() || ufos.len() != fonts.len())
+    {
+        error_and_exit(
+            clap::error::ErrorKind::WrongNumberOfValues,
+            "The --source-name argument must occur once per UFO and cannot be combined with designspace files.",
+        );
+    }
+    let source_mapping = match source_mapping {
+        Some(path) => util::load_source_mapping(path).context("can't load source mapping")?,
+        None => Vec::new(),
+    };
 
     let mut set_members = Vec::new();
     if !glyphs_files.is_empty() {
@@ -160,20 +528,87 @@ fn import(
         }
     }
 
-    for font in fonts {
-        let font = norad::Font::load(&font).expect("can't load font");
-        let source_name = util::guess_source_name(&font)
-            .expect("need a styleName in the UFO to derive a source name from");
+    let mut seen_source_names = HashSet::new();
+    let mut modified_sets = BTreeSet::new();
+    for (i, (font_path, designspace_source_name)) in ufos.iter().enumerate() {
+        let font = norad::Font::load(font_path).expect("can't load font");
+        // An explicit mapping takes precedence over designspace source names.
+        let source_name = source_names
+            .get(i)
+            .cloned()
+            .or_else(|| util::map_source_name(&source_mapping, font_path, &font))
+            .or_else(|| designspace_source_name.as_ref().map(|(_, n)| n.clone()))
+            .or_else(|| util::guess_source_name(&font))
+            .unwrap_or_else(|| {
+                error_and_exit(
+                    clap::error::ErrorKind::ValueValidation,
+                    format!(
+                        "Cannot derive a source name for '{}' as it has no styleName. Use the --source-name or --source-mapping argument.",
+                        font_path.display()
+                    ),
+                )
+            });
+        if !seen_source_names.insert(source_name.clone()) {
+            error_and_exit(
+                clap::error::ErrorKind::ValueValidation,
+                format!(
+                    "More than one UFO maps to the source name '{}'. Use the --source-name or --source-mapping argument.",
+                    source_name
+                ),
+            );
+        }
+        if let Some((index, designspace_name)) = designspace_source_name {
+            if *designspace_name != source_name {
+                documents[*index]
+                    .1
+                    .insert(designspace_name.clone(), source_name.clone());
+            }
+        }
 
+        let mut updated_font_data = false;
+        let mut diverging_features = false;
         for (set_name, import_glyphs) in &set_members {
-            fontgarden
-                .import(&font, import_glyphs, set_name, &source_name)
-                .expect("can't import font")
+            let report = fontgarden
+                .import_with_options(&font, import_glyphs, set_name, &source_name, options)
+                .with_context(|| format!("can't import {}", font_path.display()))?;
+            modified_sets.extend(report.modified_sets);
+            updated_font_data |= report.updated_font_data;
+            diverging_features |= report.diverging_features;
+            let action = match options.divergence {
+                DivergencePolicy::KeepStored => "kept the stored one",
+                _ => "imported over the stored one",
+            };
+            for glyph_name in &report.diverging_components {
+                eprintln!(
+                    "Warning: component '{glyph_name}' in source '{source_name}' differs from the stored one, {action}."
+                );
+            }
+        }
+        if !updated_font_data && !set_members.is_empty() {
+            eprintln!(
+                "Note: kept the stored font info, lib and data of source '{source_name}', as the import was restricted."
+            );
+        }
+        if diverging_features {
+            eprintln!(
+                "Warning: the feature code of {} differs from the stored one, kept the stored one.",
+                font_path.display()
+            );
         }
     }
+    for (document, renames) in &documents {
+        fontgarden.import_designspace(document, renames)?;
+    }
 
     fontgarden.save(fontgarden_path)?;
 
+    if modified_sets.is_empty() {
+        println!("No glyphs were imported.");
+    } else {
+        let set_names: Vec<&str> = modified_sets.iter().map(|n| n.as_str()).collect();
+        println!("Imported glyphs into sets: {}", set_names.join(", "));
+    }
+
     Ok(())
 }
 
@@ -184,7 +619,7 @@ fn export(
     source_names: &[Name],
     output_dir: Option<&PathBuf>,
 ) -> Result<()> {
-    let fontgarden = Fontgarden::from_path(fontgarden_path).context("can't load fontgarden")?;
+    let fontgarden = load_fontgarden(fontgarden_path)?;
 
     let coverage: HashMap<Name, HashSet<Name>> = fontgarden
         .sets
@@ -224,11 +659,7 @@ fn export(
     };
 
     let source_names: HashSet<Name> = if source_names.is_empty() {
-        let mut names = HashSet::new();
-        for set in fontgarden.sets.values() {
-            names.extend(set.sources.keys().cloned());
-        }
-        names
+        fontgarden.sources.keys().cloned().collect()
     } else {
         source_names.iter().cloned().collect()
     };
@@ -241,13 +672,249 @@ fn export(
         None => std::env::current_dir().expect("can't get current dir"),
     };
     for (ufo_name, ufo) in ufos.iter() {
-        let filename = format!("{ufo_name}.ufo");
+        let filename = match fontgarden.sources.get(ufo_name) {
+            Some(record) => format!("{}.ufo", record.filename),
+            None => format!("{ufo_name}.ufo"),
+        };
         ufo.save(output_dir.join(filename)).expect("can't save ufo");
     }
 
+    // Tie the exported UFOs together if the Fontgarden knows its design space.
+    if !fontgarden.designspace.axes.is_empty() {
+        let exported_source_names: HashSet<Name> = ufos.keys().cloned().collect();
+        let document = fontgarden.designspace.to_designspace_document(
+            &fontgarden.sources,
+            &exported_source_names,
+            &fontgarden.font_info,
+        );
+        let stem = fontgarden_path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "fontgarden".into());
+        document
+            .save(output_dir.join(format!("{stem}.designspace")))
+            .expect("can't save designspace");
+    }
+
+    Ok(())
+}
+
+fn move_glyphs(
+    fontgarden_path: &Path,
+    glyph_names: &[Name],
+    glyphs_file: Option<&Path>,
+    set_name: &Name,
+    with_components: bool,
+) -> Result<()> {
+    let mut fontgarden = load_fontgarden(fontgarden_path)?;
+
+    let mut names: HashSet<Name> = glyph_names.iter().cloned().collect();
+    if let Some(path) = glyphs_file {
+        names.extend(util::load_glyph_list(path).context("can't load glyphs file")?);
+    }
+
+    let moved = fontgarden.move_glyphs(&names, set_name, with_components)?;
+    fontgarden.save(fontgarden_path)?;
+
+    let moved: Vec<&str> = moved.iter().map(|n| n.as_str()).collect();
+    println!("Moved into set '{set_name}': {}", moved.join(", "));
+
     Ok(())
 }
 
+fn rename_glyphs(fontgarden_path: &Path, renames: &BTreeMap<Name, Name>) -> Result<()> {
+    let mut fontgarden = load_fontgarden(fontgarden_path)?;
+
+    fontgarden.rename_glyphs(renames)?;
+    fontgarden.save(fontgarden_path)?;
+
+    Ok(())
+}
+
+fn remove_glyphs(
+    fontgarden_path: &Path,
+    glyph_names: &[Name],
+    glyphs_file: Option<&Path>,
+    force: bool,
+) -> Result<()> {
+    let mut fontgarden = load_fontgarden(fontgarden_path)?;
+
+    let mut names: HashSet<Name> = glyph_names.iter().cloned().collect();
+    if let Some(path) = glyphs_file {
+        names.extend(util::load_glyph_list(path).context("can't load glyphs file")?);
+    }
+
+    if force {
+        for (dependent, used) in fontgarden.component_dependents(&names) {
+            let used: Vec<&str> = used.iter().map(|n| n.as_str()).collect();
+            eprintln!(
+                "Warning: '{dependent}' keeps using the removed {} as components.",
+                used.join(", ")
+            );
+        }
+    }
+    fontgarden.remove_glyphs(&names, force)?;
+    fontgarden.save(fontgarden_path)?;
+
+    // Feature code is left as it is, so point out where the removed names are
+    // still used.
+    let remaining: HashSet<Name> = fontgarden
+        .sets
+        .values()
+        .flat_map(|set| set.glyph_coverage())
+        .collect();
+    let is_glyph = |name: &str| names.contains(name) || remaining.contains(name);
+    let features = std::iter::once(("the prologue".to_string(), &fontgarden.features)).chain(
+        fontgarden
+            .sets
+            .iter()
+            .map(|(name, set)| (format!("set '{name}'"), &set.features)),
+    );
+    for (location, features) in features {
+        let used: BTreeSet<&str> = util::feature_glyph_names(features, is_glyph)
+            .into_iter()
+            .map(|(_, name)| name)
+            .filter(|name| names.contains(*name))
+            .collect();
+        for name in used {
+            eprintln!("Warning: the feature code of {location} still uses '{name}'.");
+        }
+    }
+
+    Ok(())
+}
+
+fn manage_sets(command: &SetCommands) -> Result<()> {
+    let fontgarden_path = match command {
+        SetCommands::New {
+            fontgarden_path, ..
+        }
+        | SetCommands::Rename {
+            fontgarden_path, ..
+        }
+        | SetCommands::Merge {
+            fontgarden_path, ..
+        }
+        | SetCommands::Split {
+            fontgarden_path, ..
+        }
+        | SetCommands::Delete {
+            fontgarden_path, ..
+        } => fontgarden_path,
+    };
+    let mut fontgarden = load_fontgarden(fontgarden_path)?;
+
+    match command {
+        SetCommands::New { name, .. } => fontgarden.new_set(name)?,
+        SetCommands::Rename {
+            old_name, new_name, ..
+        } => fontgarden.rename_set(old_name, new_name)?,
+        SetCommands::Merge { name, into, .. } => fontgarden.merge_sets(name, into)?,
+        SetCommands::Split {
+            name,
+            new_name,
+            glyphs_file,
+            script,
+            ..
+        } => {
+            let glyph_names = match (glyphs_file, script) {
+                (Some(path), _) => util::load_glyph_list(path).context("can't load glyphs file")?,
+                (_, Some(script)) => {
+                    let set = fontgarden
+                        .sets
+                        .get(name)
+                        .ok_or_else(|| anyhow::anyhow!("cannot find set named '{name}'"))?;
+                    set.glyphs_of_script(script).unwrap_or_else(|| {
+                        error_and_exit(
+                            clap::error::ErrorKind::ValueValidation,
+                            format!("Unknown script '{script}'."),
+                        )
+                    })
+                }
+                _ => unreachable!("clap requires a selection"),
+            };
+            fontgarden.split_set(name, new_name, &glyph_names)?;
+        }
+        SetCommands::Delete { name, into, .. } => fontgarden.delete_set(name, into.as_ref())?,
+    }
+
+    fontgarden.save(fontgarden_path)?;
+
+    Ok(())
+}
+
+fn manage_sources(command: &SourceCommands) -> Result<()> {
+    let fontgarden_path = match command {
+        SourceCommands::Add {
+            fontgarden_path, ..
+        }
+        | SourceCommands::Rename {
+            fontgarden_path, ..
+        }
+        | SourceCommands::Remove {
+            fontgarden_path, ..
+        }
+        | SourceCommands::Duplicate {
+            fontgarden_path, ..
+        } => fontgarden_path,
+    };
+    let mut fontgarden = load_fontgarden(fontgarden_path)?;
+
+    match command {
+        SourceCommands::Add {
+            name,
+            font,
+            location,
+            ..
+        } => {
+            let ufo = norad::Font::load(font)
+                .with_context(|| format!("can't load font {}", font.display()))?;
+            let report = fontgarden.add_source(&ufo, name, location.iter().cloned().collect())?;
+            let set_names: Vec<&str> = report.modified_sets.iter().map(|n| n.as_str()).collect();
+            println!("Imported glyphs into sets: {}", set_names.join(", "));
+            for glyph_name in &report.skipped_glyphs {
+                eprintln!(
+                    "Warning: skipped glyph '{glyph_name}', which is only in non-default layers of {}.",
+                    font.display()
+                );
+            }
+        }
+        SourceCommands::Rename {
+            old_name, new_name, ..
+        } => fontgarden.rename_source(old_name, new_name)?,
+        SourceCommands::Remove { name, .. } => fontgarden.remove_source(name)?,
+        SourceCommands::Duplicate {
+            name,
+            new_name,
+            location,
+            ..
+        } => fontgarden.duplicate_source(name, new_name, location.iter().cloned().collect())?,
+    }
+
+    fontgarden.save(fontgarden_path)?;
+
+    Ok(())
+}
+
+fn load_fontgarden(path: &Path) -> Result<Fontgarden> {
+    let (fontgarden, report) =
+        Fontgarden::from_path_with_report(path).context("can't load fontgarden")?;
+    for entry in report.ignored_source_data {
+        eprintln!("Warning: ignoring sources/{entry}, which belongs to no source in sources.csv.");
+    }
+    Ok(fontgarden)
+}
+
+fn parse_location_entry(entry: &str) -> Result<(String, f32), String> {
+    let (axis_name, value) = entry
+        .split_once('=')
+        .ok_or_else(|| format!("'{entry}' is not of the form AXIS=VALUE"))?;
+    let value = value
+        .parse()
+        .map_err(|_| format!("'{value}' is not a number"))?;
+    Ok((axis_name.to_string(), value))
+}
+
 fn error_and_exit(kind: clap::error::ErrorKind, message: impl std::fmt::Display) -> ! {
     let mut cmd = Cli::command();
     cmd.error(kind, message).exit();
@@ -1,17 +1,22 @@
 use std::{
-    collections::{BTreeMap, HashMap, HashSet},
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
     ffi::OsStr,
-    path::Path,
+    path::{Path, PathBuf},
     str::FromStr,
 };
 
 use anyhow::anyhow;
 use norad::{Codepoints, Color, Name};
 use serde::{Deserialize, Serialize};
+use unicode_script::UnicodeScript;
 
+use crate::designspace::{Designspace, LayerSource, Location};
 use crate::errors::{
-    ExportError, LoadError, LoadGlyphDataError, LoadLayerError, LoadSetError, LoadSourceError,
-    SaveError, SaveLayerError, SaveSetError, SaveSourceError,
+    DefaultLayerMismatchError, ExportError, ImportError, LoadAnchorsError,
+    LoadDesignspaceDataError, LoadDesignspaceError, LoadError, LoadFontInfoError,
+    LoadGlyphDataError, LoadLayerError, LoadMetricsError, LoadSetError, LoadSourceDataError,
+    LoadSourceError, MoveError, RemoveError, RenameError, SaveError, SaveLayerError, SaveSetError,
+    SaveSourceDataError, SaveSourceError, SetError, SourceError, WriteFontInfoError,
 };
 
 /// The top-level Fontgarden structure.
@@ -21,34 +26,201 @@ use crate::errors::{
 #[derive(Debug, Default, PartialEq)]
 pub struct Fontgarden {
     pub sets: BTreeMap<Name, Set>,
+    /// Feature code that goes before the feature code of all sets, e.g.
+    /// `languagesystem` statements.
+    pub features: String,
+    /// The font info of each source.
+    pub font_info: BTreeMap<Name, norad::FontInfo>,
+    /// The source registry, listing every source whether or not a set has
+    /// glyphs for it.
+    pub sources: BTreeMap<Name, SourceRecord>,
+    /// Image files by content hash, shared by all sets.
+    pub images: BTreeMap<String, Vec<u8>>,
+    /// Font-level lib entries with the same value in all sources.
+    pub lib: norad::Plist,
+    pub designspace: Designspace,
+    /// The UFO member order of each group, as the members of a group can be
+    /// spread over several sets.
+    pub group_order: BTreeMap<Name, Vec<Name>>,
+    /// The `public.glyphOrder` of the imported UFOs, across all sets.
+    pub glyph_order: Vec<Name>,
 }
 
 #[derive(Debug, Default, PartialEq)]
 pub struct Set {
     pub glyph_data: BTreeMap<Name, GlyphRecord>,
+    /// Group memberships of the glyphs in this set. Groups can span several
+    /// sets, each set only records its own glyphs.
+    pub groups: norad::Groups,
+    /// Feature code snippet for the glyphs in this set.
+    pub features: String,
+    /// Font-level lib entries owned by this set, e.g. settings of tools that
+    /// work on its glyphs. Keys present here are imported into this lib
+    /// instead of the shared or per-source ones. Values that differ between
+    /// sources are kept in the libs of the set's sources instead.
+    pub lib: norad::Plist,
+    /// Whether the advances of the default layers are kept in metrics.csv,
+    /// one row per glyph and two columns per source. Enabled by the presence
+    /// of the file.
+    pub metrics: bool,
+    /// Whether plain anchors are kept in anchors.csv, one row per anchor.
+    /// Enabled by the presence of the file.
+    pub anchors: bool,
     pub sources: BTreeMap<Name, Source>,
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Source {
-    // TODO: UFO layers are ordered, export from here will always sort order.
-    // Relevant other than in testing?
+    /// Layers by name. Their UFO order is kept in the source registry, see
+    /// [`SourceRecord::layer_order`].
     pub layers: BTreeMap<Name, Layer>,
+    pub kerning: norad::Kerning,
+    /// The images referenced by glyphs in this source, mapping UFO image file
+    /// names to content hashes in [`Fontgarden::images`].
+    pub images: BTreeMap<String, String>,
+    /// The values of the keys owned by the set that differ between sources,
+    /// see [`Set::lib`].
+    pub lib: norad::Plist,
 }
 
-#[derive(Debug, Default, PartialEq)]
+/// An entry of the source registry, i.e. the sources.csv table.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SourceRecord {
+    /// File name of the exported UFO, without the `.ufo` extension.
+    pub filename: String,
+    pub location: Location,
+    pub italic: bool,
+    /// Name of the default layer in every set's source directory.
+    pub default_layer: Name,
+    /// Names of the non-default layers, in UFO order.
+    pub layer_order: Vec<Name>,
+    /// Font-level lib entries specific to this source.
+    pub lib: norad::Plist,
+    /// The files of the UFO's data directory, by relative path.
+    pub data: BTreeMap<PathBuf, Vec<u8>>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
 pub struct Layer {
     pub glyphs: BTreeMap<Name, norad::Glyph>,
     pub color_marks: BTreeMap<Name, norad::Color>,
     pub default: bool,
+    /// The layer color, as in the UFO's layerinfo.plist.
+    pub color: Option<Color>,
+    /// The layer lib, as in the UFO's layerinfo.plist.
+    pub lib: norad::Plist,
+    /// Advance widths and heights kept in the set's metrics.csv instead of
+    /// the glifs. Only used for default layers of sets with metrics.
+    pub metrics: BTreeMap<Name, GlyphMetrics>,
+    /// Anchors kept in the set's anchors.csv instead of the glifs. Only used
+    /// in sets with anchors.
+    pub anchors: BTreeMap<Name, Vec<norad::Anchor>>,
+    /// Glyphs of a non-default layer that had codepoints in the UFO. They get
+    /// the codepoints of the glyph data back on export.
+    pub encoded: BTreeSet<Name>,
+}
+
+/// Glyph name, component base name and the set owning the base, as in a row
+/// of components.csv.
+type ComponentRecord = (Name, Name, Option<Name>);
+
+/// Glyph, source and layer name of a group of rows in anchors.csv.
+type AnchorsKey = (Name, Name, Name);
+
+/// What to move out of the glyphs of a UFO layer on import, see
+/// [`Layer::from_ufo_layer`].
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct LayerImportOptions {
+    /// Whether this is the default layer. Codepoints are moved into the glyph
+    /// data of the set.
+    pub default: bool,
+    /// Move advance widths and heights into [`Layer::metrics`].
+    pub metrics: bool,
+    /// Move plain anchors into [`Layer::anchors`].
+    pub anchors: bool,
+}
+
+/// Options for [`Fontgarden::import_with_options`].
+#[derive(Debug, Clone, Default)]
+pub struct ImportOptions {
+    /// What to do about glyphs pulled in as components that differ from the
+    /// stored ones.
+    pub divergence: DivergencePolicy,
+    /// Which glyphs to import, depending on whether they are already in the
+    /// Fontgarden.
+    pub mode: ImportMode,
+    /// Only touch glyphs in these sets. Importing glyphs that belong to other
+    /// sets fails, components that belong to other sets are skipped.
+    pub only_sets: Option<HashSet<Name>>,
+}
+
+/// Which glyphs to import, see [`ImportOptions::mode`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ImportMode {
+    /// Add new glyphs and update existing ones.
+    #[default]
+    All,
+    /// Only update glyphs that are already in some set.
+    UpdateExisting,
+    /// Only add glyphs that are not in any set yet.
+    AddNewOnly,
+}
+
+/// What to do when a glyph pulled in as a component of an imported glyph is
+/// already in the Fontgarden and differs from the incoming one.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DivergencePolicy {
+    /// Fail the import.
+    #[default]
+    Refuse,
+    /// Import the incoming glyph over the stored one and report it.
+    Warn,
+    /// Keep the stored glyph and report it.
+    KeepStored,
+}
+
+/// What was passed over while loading a fontgarden.
+#[derive(Debug, Default, PartialEq)]
+pub struct LoadReport {
+    /// Entries of the sources directory that belong to no registered source.
+    pub ignored_source_data: Vec<String>,
+}
+
+/// What happened during an import.
+#[derive(Debug, Default, PartialEq)]
+pub struct ImportReport {
+    /// Glyphs pulled in as components that differed from the stored ones.
+    pub diverging_components: Vec<Name>,
+    /// The sets that glyphs were imported into.
+    pub modified_sets: BTreeSet<Name>,
+    /// Whether the font info, lib and data of the source were taken from the
+    /// UFO. Restricted imports only do so for new sources.
+    pub updated_font_data: bool,
+    /// Whether the feature code of the UFO differs from the one stored for
+    /// its glyphs.
+    pub diverging_features: bool,
+    /// Glyphs of the Fontgarden that the UFO only has in non-default layers,
+    /// which were not imported. Only filled in by
+    /// [`Fontgarden::add_source`].
+    pub skipped_glyphs: Vec<Name>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct GlyphMetrics {
+    pub width: f64,
+    pub height: f64,
 }
 
 #[derive(Debug, PartialEq, Serialize, Deserialize)]
 pub(crate) struct LayerInfo {
     pub name: Name,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<Color>,
+    #[serde(default, skip_serializing_if = "norad::Plist::is_empty")]
+    pub lib: norad::Plist,
 }
 
-#[derive(Debug, Default, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
 pub struct GlyphRecord {
     pub postscript_name: Option<String>,
     #[serde(default)]
@@ -58,7 +230,7 @@ pub struct GlyphRecord {
     pub export: bool,
 }
 
-#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
 pub enum OpenTypeCategory {
     #[default]
     Unassigned = 0,
@@ -71,35 +243,277 @@ pub enum OpenTypeCategory {
 impl FromStr for OpenTypeCategory {
     type Err = &'static str;
 
+    // NOTE: Accepts the lowercase spelling used in the UFO lib, too.
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s {
-            "Unassigned" => Ok(Self::Unassigned),
-            "Base" => Ok(Self::Base),
-            "Ligature" => Ok(Self::Ligature),
-            "Mark" => Ok(Self::Mark),
-            "Component" => Ok(Self::Component),
+            "Unassigned" | "unassigned" => Ok(Self::Unassigned),
+            "Base" | "base" => Ok(Self::Base),
+            "Ligature" | "ligature" => Ok(Self::Ligature),
+            "Mark" | "mark" => Ok(Self::Mark),
+            "Component" | "component" => Ok(Self::Component),
             _ => Err("Category must be Unassigned, Base, Ligature, Mark or Component"),
         }
     }
 }
 
+impl OpenTypeCategory {
+    /// The spelling used in the `public.openTypeCategories` UFO lib key.
+    fn as_ufo_str(&self) -> &'static str {
+        match self {
+            Self::Unassigned => "unassigned",
+            Self::Base => "base",
+            Self::Ligature => "ligature",
+            Self::Mark => "mark",
+            Self::Component => "component",
+        }
+    }
+}
+
+/// Font lib keys generated from the glyph data of the sets on export.
+const GLYPH_DATA_LIB_KEYS: [&str; 4] = [
+    "public.glyphOrder",
+    "public.postscriptNames",
+    "public.openTypeCategories",
+    "public.skipExportGlyphs",
+];
+
+impl GlyphMetrics {
+    /// Move the advance width and height out of `glyph`.
+    fn take(glyph: &mut norad::Glyph) -> Self {
+        let metrics = Self {
+            width: glyph.width,
+            height: glyph.height,
+        };
+        glyph.width = 0.0;
+        glyph.height = 0.0;
+        metrics
+    }
+
+    fn apply(&self, glyph: &mut norad::Glyph) {
+        glyph.width = self.width;
+        glyph.height = self.height;
+    }
+}
+
+/// Move the anchors that fit into anchors.csv out of `glyph`, i.e. named
+/// anchors without color, identifier or lib.
+///
+/// Only the ones after the last other anchor are moved, so that appending
+/// them to the glyph again on export restores the anchor order.
+fn take_plain_anchors(glyph: &mut norad::Glyph) -> Vec<norad::Anchor> {
+    let is_plain = |anchor: &norad::Anchor| {
+        anchor.name.is_some()
+            && *anchor
+                == norad::Anchor::new(anchor.x, anchor.y, anchor.name.clone(), None, None, None)
+    };
+    let start = glyph
+        .anchors
+        .iter()
+        .rposition(|anchor| !is_plain(anchor))
+        .map_or(0, |i| i + 1);
+    glyph.anchors.split_off(start)
+}
+
+/// Encode `glyph` as a glif for saving, leaving out its codepoints. They are
+/// kept in the glyph data of a set, whatever the layer.
+fn encode_glif(glyph: &norad::Glyph) -> Result<Vec<u8>, norad::error::GlifWriteError> {
+    if glyph.codepoints.is_empty() {
+        return glyph.encode_xml();
+    }
+    let mut glyph = glyph.clone();
+    glyph.codepoints.clear();
+    glyph.encode_xml()
+}
+
+/// The encoded glifs of the glyphs of a layer, with their content hashes.
+type EncodedGlifs = BTreeMap<Name, (String, Vec<u8>)>;
+
+/// Move the entries of `map` from the old to the new names in `renames`.
+fn rename_entries<V>(map: &mut BTreeMap<Name, V>, renames: &BTreeMap<Name, Name>) {
+    for (old, new) in renames {
+        if let Some(value) = map.remove(old) {
+            map.insert(new.clone(), value);
+        }
+    }
+}
+
+/// A copy of `glyph` named `name`.
+fn renamed_glyph(glyph: norad::Glyph, name: &Name) -> norad::Glyph {
+    let mut renamed = norad::Glyph::new(name);
+    renamed.height = glyph.height;
+    renamed.width = glyph.width;
+    renamed.codepoints = glyph.codepoints;
+    renamed.note = glyph.note;
+    renamed.guidelines = glyph.guidelines;
+    renamed.anchors = glyph.anchors;
+    renamed.components = glyph.components;
+    renamed.contours = glyph.contours;
+    renamed.image = glyph.image;
+    renamed.lib = glyph.lib;
+    renamed
+}
+
+/// Remove the entries for `glyph_names` from `map` and return them.
+fn take_entries<V>(map: &mut BTreeMap<Name, V>, glyph_names: &HashSet<Name>) -> BTreeMap<Name, V> {
+    let (taken, kept) = std::mem::take(map)
+        .into_iter()
+        .partition(|(name, _)| glyph_names.contains(name));
+    *map = kept;
+    taken
+}
+
 fn default_true() -> bool {
     true
 }
 
+/// The kind of values in a row of the font_info.csv table.
+///
+/// Values that cannot be written as one of the simpler kinds, like guidelines
+/// or empty strings, are written as XML property lists.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum FontInfoValueKind {
+    String,
+    Number,
+    Boolean,
+    /// Whitespace-separated numbers in square brackets, e.g. `[-10 0 700 710]`.
+    List,
+    Plist,
+}
+
+impl FontInfoValueKind {
+    fn of(value: &plist::Value) -> Self {
+        match value {
+            plist::Value::String(s) if !s.is_empty() => Self::String,
+            plist::Value::Integer(_) | plist::Value::Real(_) => Self::Number,
+            plist::Value::Boolean(_) => Self::Boolean,
+            plist::Value::Array(values) if values.iter().all(is_plist_number) => Self::List,
+            _ => Self::Plist,
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::String => "string",
+            Self::Number => "number",
+            Self::Boolean => "boolean",
+            Self::List => "list",
+            Self::Plist => "plist",
+        }
+    }
+
+    fn encode(&self, value: &plist::Value) -> Result<String, plist::Error> {
+        let encoded = match (self, value) {
+            (Self::String, plist::Value::String(s)) => s.clone(),
+            (Self::Number, value) if is_plist_number(value) => format_plist_number(value),
+            (Self::Boolean, plist::Value::Boolean(b)) => b.to_string(),
+            (Self::List, plist::Value::Array(values)) => {
+                let numbers: Vec<String> = values.iter().map(format_plist_number).collect();
+                format!("[{}]", numbers.join(" "))
+            }
+            (_, value) => {
+                let mut buffer = Vec::new();
+                value.to_writer_xml(&mut buffer)?;
+                String::from_utf8(buffer).expect("XML property lists are valid UTF-8")
+            }
+        };
+
+        Ok(encoded)
+    }
+
+    fn decode(&self, v: &str) -> Result<plist::Value, anyhow::Error> {
+        let decoded = match self {
+            Self::String => plist::Value::String(v.into()),
+            Self::Number => parse_plist_number(v)?,
+            Self::Boolean => plist::Value::Boolean(v.parse()?),
+            Self::List => {
+                let numbers = v
+                    .strip_prefix('[')
+                    .and_then(|v| v.strip_suffix(']'))
+                    .ok_or_else(|| anyhow!("list must be enclosed in square brackets"))?;
+                let numbers: Result<Vec<_>, _> =
+                    numbers.split_whitespace().map(parse_plist_number).collect();
+                plist::Value::Array(numbers?)
+            }
+            Self::Plist => plist::Value::from_reader_xml(std::io::Cursor::new(v))?,
+        };
+
+        Ok(decoded)
+    }
+}
+
+impl FromStr for FontInfoValueKind {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "string" => Ok(Self::String),
+            "number" => Ok(Self::Number),
+            "boolean" => Ok(Self::Boolean),
+            "list" => Ok(Self::List),
+            "plist" => Ok(Self::Plist),
+            _ => Err("Value type must be string, number, boolean, list or plist"),
+        }
+    }
+}
+
+fn is_plist_number(value: &plist::Value) -> bool {
+    matches!(value, plist::Value::Integer(_) | plist::Value::Real(_))
+}
+
+fn format_plist_number(value: &plist::Value) -> String {
+    match value {
+        plist::Value::Integer(i) => match i.as_signed() {
+            Some(i) => i.to_string(),
+            None => i.as_unsigned().unwrap_or_default().to_string(),
+        },
+        plist::Value::Real(r) => r.to_string(),
+        _ => unreachable!("not a number"),
+    }
+}
+
+// NOTE: Whole numbers are always read as integers, which norad will happily
+// deserialize into float fields as well.
+fn parse_plist_number(v: &str) -> Result<plist::Value, anyhow::Error> {
+    match v.parse::<i64>() {
+        Ok(i) => Ok(plist::Value::Integer(i.into())),
+        Err(_) => Ok(plist::Value::Real(v.parse()?)),
+    }
+}
+
 impl Fontgarden {
     pub fn new() -> Self {
         Self::default()
     }
 
-    pub fn from_path(path: &Path) -> Result<Self, LoadError> {
+    /// Load a fontgarden, reporting what was passed over.
+    pub fn from_path_with_report(path: &Path) -> Result<(Self, LoadReport), LoadError> {
         let mut fontgarden = Self::new();
+        let mut report = LoadReport::default();
         let mut seen_glyph_names: HashSet<Name> = HashSet::new();
 
         if !path.is_dir() {
             return Err(LoadError::NotAFontgarden);
         }
 
+        let features_path = path.join("features.fea");
+        if features_path.exists() {
+            fontgarden.features = std::fs::read_to_string(features_path)?;
+        }
+
+        let font_info_path = path.join("font_info.csv");
+        if font_info_path.exists() {
+            fontgarden.font_info =
+                Self::load_font_info(&font_info_path).map_err(LoadError::LoadFontInfo)?;
+        }
+
+        let lib_path = path.join("lib.plist");
+        if lib_path.exists() {
+            fontgarden.lib = plist::from_file(lib_path).map_err(LoadError::LoadLib)?;
+        }
+
+        fontgarden.designspace =
+            Designspace::from_path(path).map_err(LoadError::LoadDesignspace)?;
+
         for entry in std::fs::read_dir(path)? {
             let entry = entry?;
             let path = entry.path();
@@ -129,7 +543,131 @@ impl Fontgarden {
             }
         }
 
-        Ok(fontgarden)
+        let sources_path = path.join("sources.csv");
+        if sources_path.exists() {
+            fontgarden.sources =
+                Self::load_sources(&sources_path).map_err(LoadError::LoadSources)?;
+        } else {
+            // Fontgardens without a registry only know the sources their sets
+            // have directories for.
+            for set in fontgarden.sets.values() {
+                for (source_name, source) in &set.sources {
+                    fontgarden
+                        .sources
+                        .entry(source_name.clone())
+                        .or_insert_with(|| SourceRecord {
+                            default_layer: source.default_layer_name().clone(),
+                            ..SourceRecord::new(source_name)
+                        });
+                }
+            }
+        }
+        for (set_name, set) in &fontgarden.sets {
+            for (source_name, source) in &set.sources {
+                let record = fontgarden.sources.get(source_name).ok_or_else(|| {
+                    LoadError::UnknownSource(set_name.clone(), source_name.clone())
+                })?;
+                let default_layer_name = source.default_layer_name();
+                if *default_layer_name != record.default_layer {
+                    return Err(DefaultLayerMismatchError(
+                        set_name.clone(),
+                        source_name.clone(),
+                        default_layer_name.clone(),
+                        record.default_layer.clone(),
+                    )
+                    .into());
+                }
+            }
+        }
+
+        let images_path = path.join("images");
+        if images_path.is_dir() {
+            for entry in std::fs::read_dir(images_path)? {
+                let path = entry?.path();
+                if let Some(hash) = path.file_name() {
+                    let data = std::fs::read(&path)?;
+                    fontgarden
+                        .images
+                        .insert(hash.to_string_lossy().into_owned(), data);
+                }
+            }
+        }
+        for (set_name, set) in &fontgarden.sets {
+            for (source_name, source) in &set.sources {
+                for (file_name, hash) in &source.images {
+                    if !fontgarden.images.contains_key(hash) {
+                        return Err(LoadError::MissingImage(
+                            set_name.clone(),
+                            source_name.clone(),
+                            file_name.clone(),
+                        ));
+                    }
+                }
+            }
+        }
+
+        let sources_dir = path.join("sources");
+        if sources_dir.is_dir() {
+            for entry in std::fs::read_dir(sources_dir)? {
+                let path = entry?.path();
+                let source_name = path.file_name().unwrap_or_default().to_string_lossy();
+                let record = match fontgarden.sources.get_mut(source_name.as_ref()) {
+                    Some(record) if path.is_dir() => record,
+                    _ => {
+                        report.ignored_source_data.push(source_name.into_owned());
+                        continue;
+                    }
+                };
+                record
+                    .load_lib_and_data(&path)
+                    .map_err(|e| LoadError::LoadSourceData(source_name.to_string(), e))?;
+            }
+        }
+
+        // The component tables are generated, but verify them in case glifs
+        // were edited by hand.
+        let component_tables = fontgarden.component_tables();
+        for (set_name, expected) in &component_tables {
+            let components_path = path.join(format!("set.{set_name}/components.csv"));
+            if !components_path.exists() {
+                continue;
+            }
+            let found = Self::load_components(&components_path)
+                .map_err(|e| LoadError::LoadComponents(set_name.clone(), e))?;
+            if let Some((glyph_name, base, _)) = found.symmetric_difference(expected).next() {
+                return Err(LoadError::ComponentsMismatch(
+                    set_name.clone(),
+                    glyph_name.clone(),
+                    base.clone(),
+                ));
+            }
+        }
+
+        let layer_order_path = path.join("layer_order.csv");
+        if layer_order_path.exists() {
+            let layer_order =
+                Self::load_layer_order(&layer_order_path).map_err(LoadError::LoadLayerOrder)?;
+            for (source_name, layer_names) in layer_order {
+                match fontgarden.sources.get_mut(&source_name) {
+                    Some(record) => record.layer_order = layer_names,
+                    None => return Err(LoadError::UnknownLayerOrderSource(source_name)),
+                }
+            }
+        }
+
+        let group_order_path = path.join("group_order.csv");
+        if group_order_path.exists() {
+            fontgarden.group_order =
+                Self::load_group_order(&group_order_path).map_err(LoadError::LoadGroupOrder)?;
+        }
+
+        let glyph_order_path = path.join("glyph_order.csv");
+        if glyph_order_path.exists() {
+            fontgarden.glyph_order =
+                Self::load_glyph_order(&glyph_order_path).map_err(LoadError::LoadGlyphOrder)?;
+        }
+
+        Ok((fontgarden, report))
     }
 
     pub fn save(&self, path: &Path) -> Result<(), SaveError> {
@@ -138,10 +676,154 @@ impl Fontgarden {
         }
         std::fs::create_dir(path).map_err(SaveError::CreateDir)?;
 
+        if !self.features.is_empty() {
+            std::fs::write(path.join("features.fea"), &self.features)
+                .map_err(SaveError::WriteFeatures)?;
+        }
+
+        if !self.font_info.is_empty() {
+            Self::write_font_info(&self.font_info, &path.join("font_info.csv"))
+                .map_err(SaveError::WriteFontInfo)?;
+        }
+
+        self.designspace
+            .save(path)
+            .map_err(SaveError::WriteDesignspace)?;
+
+        if !self.sources.is_empty() {
+            self.write_sources(&path.join("sources.csv"))
+                .map_err(SaveError::WriteSources)?;
+        }
+
+        if self.sources.values().any(|r| !r.layer_order.is_empty()) {
+            self.write_layer_order(&path.join("layer_order.csv"))
+                .map_err(SaveError::WriteLayerOrder)?;
+        }
+
+        if !self.group_order.is_empty() {
+            self.write_group_order(&path.join("group_order.csv"))
+                .map_err(SaveError::WriteGroupOrder)?;
+        }
+
+        if !self.glyph_order.is_empty() {
+            self.write_glyph_order(&path.join("glyph_order.csv"))
+                .map_err(SaveError::WriteGlyphOrder)?;
+        }
+
+        if !self.lib.is_empty() {
+            plist::to_file_xml(path.join("lib.plist"), &self.lib).map_err(SaveError::WriteLib)?;
+        }
+
+        for (source_name, record) in &self.sources {
+            record
+                .save_lib_and_data(&path.join("sources").join(source_name.as_str()))
+                .map_err(|e| SaveError::SaveSourceData(source_name.clone(), e))?;
+        }
+
+        // Only write images that are still referenced by some set.
+        let hashes: BTreeSet<&String> = self
+            .sets
+            .values()
+            .flat_map(|set| set.sources.values())
+            .flat_map(|source| source.images.values())
+            .collect();
+        if !hashes.is_empty() {
+            let images_path = path.join("images");
+            std::fs::create_dir(&images_path).map_err(SaveError::CreateImagesDir)?;
+            for hash in hashes {
+                if let Some(data) = self.images.get(hash) {
+                    std::fs::write(images_path.join(hash), data)
+                        .map_err(|e| SaveError::WriteImage(hash.clone(), e))?;
+                }
+            }
+        }
+
+        let mut component_tables = self.component_tables();
         for (set_name, set) in &self.sets {
             set.save(set_name, path)
                 .map_err(|e| SaveError::SaveSet(set_name.clone(), e))?;
+
+            let components = component_tables.remove(set_name).unwrap_or_default();
+            if !components.is_empty() {
+                let components_path = path.join(format!("set.{set_name}/components.csv"));
+                Self::write_components(&components_path, &components)
+                    .map_err(|e| SaveError::WriteComponents(set_name.clone(), e))?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The components used by the glyphs of each set in any source or layer,
+    /// along with the set that owns each component base, if any.
+    fn component_tables(&self) -> BTreeMap<Name, BTreeSet<ComponentRecord>> {
+        let owners = self.glyph_owners();
+
+        let mut tables = BTreeMap::new();
+        for (set_name, set) in &self.sets {
+            let mut table = BTreeSet::new();
+            for source in set.sources.values() {
+                for layer in source.layers.values() {
+                    for (glyph_name, glyph) in &layer.glyphs {
+                        for component in &glyph.components {
+                            table.insert((
+                                glyph_name.clone(),
+                                component.base.clone(),
+                                owners.get(&component.base).cloned(),
+                            ));
+                        }
+                    }
+                }
+            }
+            tables.insert(set_name.clone(), table);
+        }
+
+        tables
+    }
+
+    /// The names of the groups with members in any set.
+    fn group_names(&self) -> HashSet<Name> {
+        self.sets
+            .values()
+            .flat_map(|set| set.groups.keys().cloned())
+            .collect()
+    }
+
+    /// The set each glyph of the Fontgarden belongs to.
+    fn glyph_owners(&self) -> HashMap<Name, Name> {
+        self.sets
+            .iter()
+            .flat_map(|(set_name, set)| {
+                set.glyph_coverage()
+                    .into_iter()
+                    .map(move |n| (n, set_name.clone()))
+            })
+            .collect()
+    }
+
+    fn load_components(path: &Path) -> Result<BTreeSet<ComponentRecord>, csv::Error> {
+        let mut components = BTreeSet::new();
+
+        let mut reader = csv::Reader::from_path(path)?;
+        for result in reader.deserialize() {
+            let record: ComponentRecord = result?;
+            components.insert(record);
+        }
+
+        Ok(components)
+    }
+
+    fn write_components(
+        path: &Path,
+        components: &BTreeSet<ComponentRecord>,
+    ) -> Result<(), csv::Error> {
+        let mut writer = csv::Writer::from_path(path)?;
+
+        writer.write_record(["glyph", "base", "base_set"])?;
+        for record in components {
+            writer.serialize(record)?;
         }
+        writer.flush()?;
 
         Ok(())
     }
@@ -150,17 +832,57 @@ impl Fontgarden {
     ///
     /// Strategy: for each imported glyph, if the name already exists in some
     /// set, import it there, else import it into `set_name`.
-    pub fn import(
+    ///
+    /// The default layer of the UFO is imported under the default layer name
+    /// of the source in the registry, whatever its name in the UFO.
+    ///
+    /// Glyphs used as components by the imported glyphs are imported, too.
+    /// If such a glyph is already stored for the source and differs from the
+    /// incoming one in any layer, e.g. when importing A-cy from a UFO with a
+    /// different A, `options.divergence` decides what happens.
+    ///
+    /// On error, the Fontgarden is left unchanged.
+    pub fn import_with_options(
         &mut self,
         font: &norad::Font,
         glyphs: &HashSet<Name>,
         set_name: &Name,
         source_name: &Name,
-    ) -> Result<(), LoadError> {
+        options: &ImportOptions,
+    ) -> Result<ImportReport, ImportError> {
+        let mut report = ImportReport::default();
+        let default_layer_name = match self.sources.get(source_name) {
+            Some(record) => record.default_layer.clone(),
+            None => font.default_layer().name().clone(),
+        };
+        if font
+            .iter_layers()
+            .any(|layer| layer != font.default_layer() && *layer.name() == default_layer_name)
+        {
+            return Err(ImportError::DefaultLayerConflict(
+                source_name.clone(),
+                default_layer_name,
+            ));
+        }
+        for (name, set) in &self.sets {
+            if let Some(source) = set.sources.get(source_name) {
+                if *source.default_layer_name() != default_layer_name {
+                    return Err(DefaultLayerMismatchError(
+                        name.clone(),
+                        source_name.clone(),
+                        source.default_layer_name().clone(),
+                        default_layer_name,
+                    )
+                    .into());
+                }
+            }
+        }
+
         // Also import all glyphs used as components in the glyph list.
         //
         // TODO: Write test that exercises different layers referencing
         // different things.
+        let requested_glyphs = glyphs;
         let mut glyphs = glyphs.clone();
         for layer in font.layers.iter() {
             let components_in_glyph = |name: Name| {
@@ -175,7 +897,116 @@ impl Fontgarden {
             ));
         }
 
+        let owners = self.glyph_owners();
+        match options.mode {
+            ImportMode::All => (),
+            ImportMode::UpdateExisting => glyphs.retain(|name| owners.contains_key(name)),
+            ImportMode::AddNewOnly => glyphs.retain(|name| !owners.contains_key(name)),
+        }
+        if let Some(only_sets) = &options.only_sets {
+            let outside = |name: &Name| owners.get(name).is_some_and(|s| !only_sets.contains(s));
+            let mut requested_outside: Vec<Name> = requested_glyphs
+                .iter()
+                .filter(|name| glyphs.contains(*name) && outside(name))
+                .cloned()
+                .collect();
+            requested_outside.sort();
+            if !requested_outside.is_empty() {
+                return Err(ImportError::GlyphsInOtherSets(requested_outside));
+            }
+            // Components from other sets are left as they are.
+            glyphs.retain(|name| !outside(name));
+        }
+
+        let components: HashSet<Name> = glyphs.difference(requested_glyphs).cloned().collect();
+        let mut diverging: Vec<Name> = self
+            .diverging_glyphs(font, &components, source_name)
+            .into_iter()
+            .collect();
+        diverging.sort();
+        if !diverging.is_empty() {
+            match options.divergence {
+                DivergencePolicy::Refuse => {
+                    return Err(ImportError::DivergingComponents(
+                        source_name.clone(),
+                        diverging,
+                    ));
+                }
+                DivergencePolicy::Warn => (),
+                DivergencePolicy::KeepStored => {
+                    glyphs.retain(|name| !diverging.contains(name));
+                }
+            }
+            report.diverging_components = diverging;
+        }
+
         let mut glyph_data = crate::util::extract_glyph_data(font, &glyphs);
+        let ufo_glyph_order = crate::util::ufo_glyph_order(font)?;
+
+        let mut data = BTreeMap::new();
+        let data_paths: Vec<PathBuf> = font.data.keys().cloned().collect();
+        for path in data_paths {
+            let contents = font
+                .data
+                .get(&path)
+                .expect("path was listed by the store")
+                .map_err(|e| ImportError::LoadData(path.clone(), e))?;
+            data.insert(path, contents.to_vec());
+        }
+
+        // Load the images referenced by the imported glyphs before anything
+        // is changed, so that a failing import leaves the Fontgarden as it
+        // was.
+        let mut images: HashMap<PathBuf, Vec<u8>> = HashMap::new();
+        for layer in font.iter_layers() {
+            for glyph in layer.iter().filter(|g| glyphs.contains(g.name())) {
+                let file_name = match &glyph.image {
+                    Some(image) => &image.file_name,
+                    None => continue,
+                };
+                // NOTE: Dangling image references are kept as they are.
+                if let Some(image_data) = font.images.get(file_name) {
+                    let image_data =
+                        image_data.map_err(|e| ImportError::LoadImage(file_name.clone(), e))?;
+                    images.insert(file_name.clone(), image_data.to_vec());
+                }
+            }
+        }
+
+        // Font-level data is only taken over by imports of all glyphs, or
+        // when the source is new.
+        let restricted = options.mode != ImportMode::All || options.only_sets.is_some();
+        report.updated_font_data = !restricted || !self.sources.contains_key(source_name);
+        let record = self
+            .sources
+            .entry(source_name.clone())
+            .or_insert_with(|| SourceRecord::from_font(font, source_name));
+        record.update_layer_order(font);
+        if report.updated_font_data {
+            record.data = data;
+            self.font_info
+                .insert(source_name.clone(), font.font_info.clone());
+            self.import_lib(font, source_name);
+        }
+
+        // Take over the member order of the groups with imported glyphs.
+        // Members known from previous imports but missing from `font` go
+        // last.
+        for (group_name, members) in &font.groups {
+            if !members.iter().any(|name| glyphs.contains(name)) {
+                continue;
+            }
+            let recorded = self.group_order.remove(group_name).unwrap_or_default();
+            let mut group_order = members.clone();
+            for name in recorded {
+                if !group_order.contains(&name) {
+                    group_order.push(name);
+                }
+            }
+            self.group_order.insert(group_name.clone(), group_order);
+        }
+        self.glyph_order =
+            crate::util::merge_glyph_order(&self.glyph_order, &ufo_glyph_order, &glyphs);
 
         // Check if some glyphs are already in other sets so we can route them
         // there. Fresh glyphs without an entry can then go into `set_name`.
@@ -193,6 +1024,7 @@ impl Fontgarden {
         if !glyphs_leftovers.is_empty() {
             set_to_glyphs.insert(set_name.clone(), glyphs_leftovers);
         }
+        report.modified_sets = set_to_glyphs.keys().cloned().collect();
 
         for (set_name, glyph_names) in set_to_glyphs {
             let set = self.sets.entry(set_name.clone()).or_default();
@@ -202,13 +1034,33 @@ impl Fontgarden {
                 }
             }
 
-            let source = set.sources.entry(source_name.clone()).or_insert_with(|| {
-                Source::new_with_default_layer_name(font.default_layer().name().clone())
-            });
+            // Replace the group memberships of the imported glyphs.
+            for members in set.groups.values_mut() {
+                members.retain(|name| !glyph_names.contains(name));
+            }
+            for (group_name, members) in &font.groups {
+                let our_members = members.iter().filter(|name| glyph_names.contains(*name));
+                set.groups
+                    .entry(group_name.clone())
+                    .or_default()
+                    .extend(our_members.cloned());
+            }
+            set.groups.retain(|_, members| !members.is_empty());
+
+            let source = set
+                .sources
+                .entry(source_name.clone())
+                .or_insert_with(|| Source::new_with_default_layer_name(default_layer_name.clone()));
             assert_eq!(source.layers.values().filter(|l| l.default).count(), 1);
 
             for layer in font.iter_layers() {
-                let our_layer = Layer::from_ufo_layer(layer, &glyph_names);
+                let default = layer == font.default_layer();
+                let options = LayerImportOptions {
+                    default,
+                    metrics: default && set.metrics,
+                    anchors: set.anchors,
+                };
+                let our_layer = Layer::from_ufo_layer(layer, &glyph_names, options);
                 if our_layer.glyphs.is_empty() {
                     continue;
                 }
@@ -219,781 +1071,5440 @@ impl Fontgarden {
                     source.get_or_create_layer(layer.name().clone())
                 };
 
+                let unencoded = our_layer.glyphs_without_codepoints();
                 target_layer.glyphs.extend(our_layer.glyphs);
                 target_layer.color_marks.extend(our_layer.color_marks);
+                target_layer.metrics.extend(our_layer.metrics);
+                target_layer.anchors.extend(our_layer.anchors);
+                for name in unencoded {
+                    target_layer.encoded.remove(&name);
+                }
+                target_layer.encoded.extend(our_layer.encoded);
+                target_layer.color = our_layer.color;
+                target_layer.lib = our_layer.lib;
             }
-        }
 
-        // TODO: Import glyphs used as components by glyphs on the import list
-        // automatically (recursively follow the graph).
+            // Store the images referenced by the imported glyphs once, by
+            // content hash.
+            for layer in font.iter_layers() {
+                for glyph in layer.iter().filter(|g| glyph_names.contains(g.name())) {
+                    let file_name = match &glyph.image {
+                        Some(image) => &image.file_name,
+                        None => continue,
+                    };
+                    if let Some(data) = images.get(file_name) {
+                        let hash = crate::util::insert_by_content(&mut self.images, data);
+                        source
+                            .images
+                            .insert(file_name.to_string_lossy().into_owned(), hash);
+                    }
+                }
+            }
 
-        // TODO: Check incoming composites with components outside the import
-        // set name: are they different? If so, warn the user. E.g. you import
-        // A-cy into Cyrl and the underlying A is different from the A in the
-        // import font. Again track diffs recursively in nested composites.
+            // Kerning pairs with a glyph as the first side are owned by the
+            // set of the glyph. Replace the whole first side so that pairs
+            // deleted in the UFO disappear here, too.
+            for (first, seconds) in &font.kerning {
+                if glyph_names.contains(first) {
+                    source.kerning.insert(first.clone(), seconds.clone());
+                }
+            }
+        }
 
-        Ok(())
+        // Pairs with a group as the first side are stored once, see
+        // [`Self::group_kerning_owner`]. Again, replace the whole first side.
+        for (first, seconds) in &font.kerning {
+            let imported = font
+                .groups
+                .get(first)
+                .is_some_and(|members| members.iter().any(|m| glyphs.contains(m)));
+            if !imported {
+                continue;
+            }
+            for set in self.sets.values_mut() {
+                if let Some(source) = set.sources.get_mut(source_name) {
+                    source.kerning.remove(first);
+                }
+            }
+            if let Some(owner) = self.group_kerning_owner(first, source_name).cloned() {
+                self.sets
+                    .get_mut(&owner)
+                    .and_then(|set| set.sources.get_mut(source_name))
+                    .expect("the owner has the source")
+                    .kerning
+                    .insert(first.clone(), seconds.clone());
+            }
+        }
+        self.place_group_kerning();
+        self.prune_group_order();
+
+        // Feature code is maintained in the fontgarden. The one of the UFO is
+        // only taken over as the prologue while there is none at all.
+        let no_features = self.features.trim().is_empty()
+            && self.sets.values().all(|set| set.features.trim().is_empty());
+        if report.updated_font_data && no_features {
+            self.features = font.features.clone();
+        } else {
+            let font_glyphs: HashSet<Name> = font
+                .default_layer()
+                .iter()
+                .map(|g| g.name().clone())
+                .collect();
+            let stored_features = self.assemble_features(&font_glyphs);
+            report.diverging_features = stored_features.trim() != font.features.trim();
+        }
+
+        Ok(report)
     }
 
-    fn assemble_sources(&self, source_names: &HashSet<Name>) -> HashMap<Name, Source> {
-        let mut assembled_sources: HashMap<Name, Source> = HashMap::new();
+    /// The set that stores the kerning pairs with `group_name` as the first
+    /// side in `source_name`: the first set with members of the group that
+    /// has the source.
+    fn group_kerning_owner(&self, group_name: &Name, source_name: &Name) -> Option<&Name> {
+        self.sets
+            .iter()
+            .find(|(_, set)| {
+                set.groups.contains_key(group_name) && set.sources.contains_key(source_name)
+            })
+            .map(|(set_name, _)| set_name)
+    }
 
+    /// Drop the members from the recorded group member order that no set
+    /// lists in the group anymore, and the groups left without members.
+    fn prune_group_order(&mut self) {
+        let mut memberships: HashMap<&Name, HashSet<&Name>> = HashMap::new();
         for set in self.sets.values() {
-            for (source_name, source) in set
-                .sources
-                .iter()
-                .filter(|(name, _)| source_names.contains(*name))
-            {
-                let assembled_source = assembled_sources.entry(source_name.clone()).or_default();
-                for (layer_name, layer) in source.layers.iter() {
-                    let assembled_layer = assembled_source
-                        .layers
-                        .entry(layer_name.clone())
-                        .or_default();
-                    assembled_layer.glyphs.extend(layer.glyphs.clone());
-                    assembled_layer
-                        .color_marks
-                        .extend(layer.color_marks.clone());
-                    // TODO: guard against different default layers having different names?
-                    assembled_layer.default = layer.default;
+            for (group_name, members) in &set.groups {
+                memberships.entry(group_name).or_default().extend(members);
+            }
+        }
+        self.group_order
+            .retain(|group_name, members| match memberships.get(group_name) {
+                Some(set_members) => {
+                    members.retain(|name| set_members.contains(name));
+                    !members.is_empty()
+                }
+                None => false,
+            });
+    }
+
+    /// Move kerning pairs with a group as the first side into the set of
+    /// [`Self::group_kerning_owner`], after glyphs changed sets or groups.
+    /// Pairs of groups without members are dropped.
+    fn place_group_kerning(&mut self) {
+        // As on import, a first side is a group if it names one, whatever its
+        // prefix. First sides that are neither a group with members nor a
+        // glyph belong to groups that lost all their members.
+        let group_names = self.group_names();
+        let owners = self.glyph_owners();
+        let mut misplaced = Vec::new();
+        for (set_name, set) in &self.sets {
+            for (source_name, source) in &set.sources {
+                for first in source.kerning.keys() {
+                    if !group_names.contains(first) && owners.contains_key(first) {
+                        continue;
+                    }
+                    let owner = self.group_kerning_owner(first, source_name);
+                    if owner != Some(set_name) {
+                        misplaced.push((
+                            set_name.clone(),
+                            source_name.clone(),
+                            first.clone(),
+                            owner.cloned(),
+                        ));
+                    }
                 }
             }
         }
 
-        assembled_sources
+        for (set_name, source_name, first, owner) in misplaced {
+            let source = self
+                .sets
+                .get_mut(&set_name)
+                .and_then(|set| set.sources.get_mut(&source_name))
+                .expect("collected above");
+            let seconds = source.kerning.remove(&first).expect("collected above");
+            if let Some(owner) = owner {
+                self.sets
+                    .get_mut(&owner)
+                    .and_then(|set| set.sources.get_mut(&source_name))
+                    .expect("the owner has the source")
+                    .kerning
+                    .entry(first)
+                    .or_default()
+                    .extend(seconds);
+            }
+        }
     }
 
-    pub fn export(
+    /// The glyphs in `glyph_names` that are stored for `source_name` and
+    /// differ from the ones in `font` in some layer, ignoring mark colors.
+    fn diverging_glyphs(
         &self,
+        font: &norad::Font,
         glyph_names: &HashSet<Name>,
-        source_names: &HashSet<Name>,
-    ) -> Result<BTreeMap<Name, norad::Font>, ExportError> {
-        let mut ufos: BTreeMap<Name, norad::Font> = BTreeMap::new();
+        source_name: &Name,
+    ) -> HashSet<Name> {
+        let mut diverging = HashSet::new();
+        for set in self.sets.values() {
+            let source = match set.sources.get(source_name) {
+                Some(source) => source,
+                None => continue,
+            };
+            let coverage = set.glyph_coverage();
+            let stored_names: HashSet<Name> = coverage.intersection(glyph_names).cloned().collect();
+            if stored_names.is_empty() {
+                continue;
+            }
 
-        // First, make a copy of self and resolve the glyph list to include all
-        // referenced components. Make the union set up front across all layers
-        // because not every layer by itself contains all glyphs.
-        //
-        // TODO: Be smarter by resolving from the Fontgarden instead of making a
-        // copy of self and then pruning it?
-        let mut sources = self.assemble_sources(source_names);
-        let mut glyph_names = glyph_names.clone();
-        for source in sources.values() {
-            for layer in source.layers.values() {
-                let components_in_glyph = |n| {
-                    layer
-                        .glyphs
-                        .get(&n)
-                        .map(|g| g.components.iter().map(|c| c.base.clone()).collect())
-                        .unwrap_or_default()
+            for layer in font.iter_layers() {
+                let default = layer == font.default_layer();
+                let stored_layer = if default {
+                    source.get_default_layer()
+                } else {
+                    match source.layers.get(layer.name()) {
+                        Some(layer) => layer,
+                        None => continue,
+                    }
                 };
-
-                glyph_names.extend(crate::util::glyphset_follow_composites(
-                    &glyph_names,
-                    components_in_glyph,
-                ));
+                let options = LayerImportOptions {
+                    default,
+                    metrics: default && set.metrics,
+                    anchors: set.anchors,
+                };
+                let incoming_layer = Layer::from_ufo_layer(layer, &stored_names, options);
+                for name in incoming_layer.glyphs.keys() {
+                    if stored_layer.glyphs.contains_key(name)
+                        && !stored_layer.same_glyph(&incoming_layer, name)
+                    {
+                        diverging.insert(name.clone());
+                    }
+                }
             }
         }
+        diverging
+    }
 
-        // Next, prune sources and glyphs not in the resolved glyph list.
-        for source in sources.values_mut() {
-            for layer in source.layers.values_mut() {
-                layer.glyphs.retain(|name, _| glyph_names.contains(name));
-                layer
-                    .color_marks
-                    .retain(|name, _| glyph_names.contains(name));
-            }
+    /// Distribute the font-level lib of `font`, imported as `source_name`.
+    ///
+    /// Keys owned by a set go into the set's lib if they have the same value
+    /// in all sources, into the libs of the set's sources otherwise. Of the
+    /// rest, the keys with the same value in all sources go into the shared
+    /// lib, the others into the libs of the sources. Keys generated from
+    /// glyph data are dropped.
+    fn import_lib(&mut self, font: &norad::Font, source_name: &Name) {
+        let mut incoming = font.lib.clone();
+        for key in GLYPH_DATA_LIB_KEYS {
+            incoming.remove(key);
         }
-
-        // Then, transform the pruned tree into UFO structures.
-        for (source_name, source) in sources {
-            let ufo = ufos.entry(source_name.clone()).or_default();
-            for (layer_name, layer) in source.layers {
-                if layer.glyphs.is_empty() {
-                    continue;
+        for set in self.sets.values_mut() {
+            for key in set.owned_lib_keys() {
+                let value = match incoming.remove(&key) {
+                    Some(value) => value,
+                    None => continue,
+                };
+                let shared = set.lib.remove(&key);
+                let mut values: BTreeMap<&Name, Option<plist::Value>> = BTreeMap::new();
+                for name in self.sources.keys() {
+                    let own = set.sources.get(name).and_then(|s| s.lib.get(&key));
+                    values.insert(name, own.or(shared.as_ref()).cloned());
                 }
+                values.insert(source_name, Some(value.clone()));
 
-                if layer.default {
-                    {
-                        let ufo_layer = ufo.layers.default_layer_mut();
-                        layer.into_ufo_layer(ufo_layer);
-                    }
-                    // TODO: be smarter about naming default layers?
-                    if layer_name != *ufo.layers.default_layer_mut().name() {
-                        ufo.layers
-                            .rename_layer(
-                                &ufo.layers.default_layer().name().clone(),
-                                &layer_name,
-                                false,
-                            )
-                            .unwrap();
+                if values.values().all(|v| v.as_ref() == Some(&value)) {
+                    for source in set.sources.values_mut() {
+                        source.lib.remove(&key);
                     }
-                } else {
-                    let ufo_layer = match ufo.layers.get_mut(&layer_name) {
-                        Some(ufo_layer) => ufo_layer,
-                        None => ufo
-                            .layers
-                            .new_layer(&layer_name)
-                            .expect("can't make new layer"),
+                    set.lib.insert(key, value);
+                    continue;
+                }
+                for (name, value) in values {
+                    let source = match set.sources.get_mut(name) {
+                        Some(source) => source,
+                        None if value.is_some() => {
+                            // NOTE: A source without glyphs in the set still
+                            // keeps its values of the set's keys.
+                            let default_layer = self.sources.get(name).map_or_else(
+                                || font.default_layer().name().clone(),
+                                |record| record.default_layer.clone(),
+                            );
+                            set.sources.entry(name.clone()).or_insert_with(|| {
+                                Source::new_with_default_layer_name(default_layer)
+                            })
+                        }
+                        None => continue,
+                    };
+                    match value {
+                        Some(value) => source.lib.insert(key.clone(), value),
+                        None => source.lib.remove(&key),
                     };
-                    layer.into_ufo_layer(ufo_layer);
                 }
             }
         }
+        let owned_keys: HashSet<String> = self
+            .sets
+            .values()
+            .flat_map(|s| s.owned_lib_keys())
+            .collect();
+
+        let mut libs: BTreeMap<Name, norad::Plist> = BTreeMap::new();
+        for (name, record) in &self.sources {
+            let mut lib = self.lib.clone();
+            lib.extend(record.lib.clone());
+            libs.insert(name.clone(), lib);
+        }
+        libs.insert(source_name.clone(), incoming);
+        for lib in libs.values_mut() {
+            for key in &owned_keys {
+                lib.remove(key);
+            }
+        }
 
-        Ok(ufos)
+        let mut shared = norad::Plist::new();
+        if let Some(first) = libs.values().next() {
+            for (key, value) in first.iter() {
+                if libs.values().all(|lib| lib.get(key) == Some(value)) {
+                    shared.insert(key.clone(), value.clone());
+                }
+            }
+        }
+        for (name, lib) in libs {
+            if let Some(record) = self.sources.get_mut(&name) {
+                record.lib = lib
+                    .into_iter()
+                    .filter(|(key, _)| !shared.contains_key(key))
+                    .collect();
+            }
+        }
+        self.lib = shared;
     }
-}
-
-impl Set {
-    fn from_path(path: &Path) -> Result<Self, LoadSetError> {
-        let glyph_data = Self::load_glyph_data(&path.join("glyph_data.csv"))
-            .map_err(LoadSetError::LoadGlyphData)?;
 
-        let mut sources = BTreeMap::new();
-        for entry in std::fs::read_dir(path)? {
-            let entry = entry?;
-            let path = entry.path();
-            let metadata = entry.metadata()?;
-            if metadata.is_dir() {
-                // TODO: Figure out when this call is None and if we should deal
-                // with it.
-                if let Some(file_name) = path.file_name() {
-                    if let Some(source_name) = file_name.to_string_lossy().strip_prefix("source.") {
-                        let source_name = Name::new(source_name)
-                            .map_err(|e| LoadSetError::NamingError(source_name.into(), e))?;
-                        let source = Source::from_path(&path)
-                            .map_err(|e| LoadSetError::LoadSource(source_name.clone(), e))?;
-                        sources.insert(source_name, source);
-                    }
-                }
+    /// Merge the design space of a designspace document into the stored one,
+    /// see [`Designspace::merge`], registering the location and filename stem
+    /// of each of its UFO sources.
+    ///
+    /// `renames` maps designspace source names to the names the UFOs were
+    /// imported under, if they differ.
+    pub fn import_designspace(
+        &mut self,
+        document: &norad::designspace::DesignSpaceDocument,
+        renames: &HashMap<Name, Name>,
+    ) -> Result<(), LoadDesignspaceError> {
+        let mut designspace = Designspace::from_designspace_document(document)?;
+        for layer_source in designspace.layer_sources.values_mut() {
+            if let Some(new) = renames.get(&layer_source.source) {
+                layer_source.source = new.clone();
+            }
+        }
+        // Check all source names before changing anything.
+        let sources = document
+            .sources
+            .iter()
+            .filter(|s| s.layer.is_none())
+            .map(|source| Ok((source, crate::util::designspace_source_name(source)?)))
+            .collect::<Result<Vec<_>, LoadDesignspaceError>>()?;
+        self.designspace.merge(designspace)?;
+
+        for (source, source_name) in sources {
+            let source_name = renames.get(&source_name).cloned().unwrap_or(source_name);
+            let record = self
+                .sources
+                .entry(source_name.clone())
+                .or_insert_with(|| SourceRecord::new(&source_name));
+            record.location = crate::designspace::location_from_norad(&source.location);
+            if let Some(stem) = Path::new(&source.filename).file_stem() {
+                record.filename = stem.to_string_lossy().into_owned();
             }
         }
 
-        Ok(Set {
-            glyph_data,
-            sources,
-        })
+        Ok(())
     }
 
-    pub fn save(&self, set_name: &Name, root_path: &Path) -> Result<(), SaveSetError> {
-        let set_path = root_path.join(format!("set.{set_name}"));
-        std::fs::create_dir(&set_path).map_err(SaveSetError::CreateDir)?;
+    /// Move glyphs into the set `target`, creating it if necessary.
+    ///
+    /// Everything stored for the glyphs moves along, see [`Set::split_off`].
+    /// With `with_components`, glyphs used as components only by the moved
+    /// glyphs move, too. Returns the glyphs that changed sets.
+    pub fn move_glyphs(
+        &mut self,
+        glyph_names: &HashSet<Name>,
+        target: &Name,
+        with_components: bool,
+    ) -> Result<BTreeSet<Name>, MoveError> {
+        let owners = self.glyph_owners();
+        let mut unknown: Vec<Name> = glyph_names
+            .iter()
+            .filter(|name| !owners.contains_key(*name))
+            .cloned()
+            .collect();
+        if !unknown.is_empty() {
+            unknown.sort();
+            return Err(MoveError::UnknownGlyphs(unknown));
+        }
 
-        Self::write_glyph_data(&self.glyph_data, &set_path.join("glyph_data.csv"))
-            .map_err(SaveSetError::WriteGlyphData)?;
+        let glyph_names = if with_components {
+            self.with_exclusive_components(glyph_names)
+        } else {
+            glyph_names.clone()
+        };
 
-        for (source_name, source) in &self.sources {
-            source
-                .save(source_name, &set_path)
-                .map_err(|e| SaveSetError::SaveSource(source_name.clone(), e))?;
+        let mut moved = BTreeSet::new();
+        let mut set_to_glyphs: BTreeMap<Name, HashSet<Name>> = BTreeMap::new();
+        for name in glyph_names {
+            if let Some(owner) = owners.get(&name).filter(|owner| *owner != target) {
+                set_to_glyphs
+                    .entry(owner.clone())
+                    .or_default()
+                    .insert(name.clone());
+                moved.insert(name);
+            }
+        }
+        for (set_name, names) in set_to_glyphs {
+            let split = self
+                .sets
+                .get_mut(&set_name)
+                .expect("glyph owners must exist")
+                .split_off(&names);
+            // A new target set keeps the metrics and anchors tables of the
+            // first set glyphs are moved out of, like a split set does.
+            self.sets
+                .entry(target.clone())
+                .or_insert_with(|| Set {
+                    metrics: split.metrics,
+                    anchors: split.anchors,
+                    ..Default::default()
+                })
+                .merge(split);
         }
+        self.place_group_kerning();
+        self.prune_group_order();
 
-        Ok(())
+        Ok(moved)
     }
 
-    pub fn glyph_coverage(&self) -> HashSet<Name> {
-        let mut glyphs = HashSet::new();
-        glyphs.extend(self.glyph_data.keys().cloned());
-        for source in self.sources.values() {
-            for layer in source.layers.values() {
-                glyphs.extend(layer.glyphs.keys().cloned());
+    /// Rename glyphs from the old to the new names in `renames`.
+    ///
+    /// The glyphs are renamed in all sources and layers, along with their
+    /// glyph data, group memberships and kerning. Components referencing them
+    /// are rewritten in all sets, and so are the glyph names in the feature
+    /// code, see [`crate::util::rename_glyphs_in_features`].
+    pub fn rename_glyphs(&mut self, renames: &BTreeMap<Name, Name>) -> Result<(), RenameError> {
+        let owners = self.glyph_owners();
+        let mut new_names = HashSet::new();
+        for (old, new) in renames {
+            if !owners.contains_key(old) {
+                return Err(RenameError::UnknownGlyph(old.clone()));
+            }
+            if owners.contains_key(new) || !new_names.insert(new) {
+                return Err(RenameError::NameCollision(old.clone(), new.clone()));
             }
         }
-        glyphs
-    }
 
-    fn load_glyph_data(path: &Path) -> Result<BTreeMap<Name, GlyphRecord>, LoadGlyphDataError> {
-        let mut glyph_data = BTreeMap::new();
-        let mut reader = csv::Reader::from_path(path).map_err(LoadGlyphDataError::Csv)?;
+        let is_glyph = |name: &str| owners.contains_key(name);
+        self.features = crate::util::rename_glyphs_in_features(&self.features, renames, is_glyph);
+        for members in self.group_order.values_mut() {
+            for member in members.iter_mut() {
+                if let Some(new) = renames.get(&*member) {
+                    *member = new.clone();
+                }
+            }
+        }
+        for name in self.glyph_order.iter_mut() {
+            if let Some(new) = renames.get(&*name) {
+                *name = new.clone();
+            }
+        }
+        for set in self.sets.values_mut() {
+            set.features = crate::util::rename_glyphs_in_features(&set.features, renames, is_glyph);
+            rename_entries(&mut set.glyph_data, renames);
+            for members in set.groups.values_mut() {
+                for member in members.iter_mut() {
+                    if let Some(new) = renames.get(&*member) {
+                        *member = new.clone();
+                    }
+                }
+            }
+            for source in set.sources.values_mut() {
+                for layer in source.layers.values_mut() {
+                    for (old, new) in renames {
+                        if let Some(glyph) = layer.glyphs.remove(old) {
+                            layer.glyphs.insert(new.clone(), renamed_glyph(glyph, new));
+                        }
+                    }
+                    rename_entries(&mut layer.color_marks, renames);
+                    rename_entries(&mut layer.metrics, renames);
+                    rename_entries(&mut layer.anchors, renames);
+                    for (old, new) in renames {
+                        if layer.encoded.remove(old) {
+                            layer.encoded.insert(new.clone());
+                        }
+                    }
+                    for glyph in layer.glyphs.values_mut() {
+                        for component in glyph.components.iter_mut() {
+                            if let Some(new) = renames.get(&component.base) {
+                                component.base = new.clone();
+                            }
+                        }
+                    }
+                }
+                rename_entries(&mut source.kerning, renames);
+                for seconds in source.kerning.values_mut() {
+                    rename_entries(seconds, renames);
+                }
+            }
+        }
+        self.prune_group_order();
 
-        type Record = (String, Option<String>, Option<String>, Option<String>, bool);
-        for result in reader.deserialize() {
-            let record: Record = result.map_err(LoadGlyphDataError::Csv)?;
+        Ok(())
+    }
 
-            let glyph_name = Name::new(&record.0)
-                .map_err(|e| LoadGlyphDataError::InvalidGlyphName(record.0, e))?;
-            let codepoints = match &record.2 {
-                Some(codepoints_string) => {
-                    Self::parse_codepoints(codepoints_string).map_err(|e| {
-                        LoadGlyphDataError::InvalidCodepoint(
-                            glyph_name.clone(),
-                            codepoints_string.clone(),
-                            e,
-                        )
-                    })?
-                }
-                None => norad::Codepoints::new([]),
-            };
+    /// Remove glyphs from the Fontgarden, along with everything stored for them
+    /// and the kerning pairs they are part of.
+    ///
+    /// Fails if other glyphs use them as components, directly or through
+    /// nested components, unless `force` is set. The other glyphs then keep
+    /// their now dangling components.
+    pub fn remove_glyphs(
+        &mut self,
+        glyph_names: &HashSet<Name>,
+        force: bool,
+    ) -> Result<(), RemoveError> {
+        let owners = self.glyph_owners();
+        let mut unknown: Vec<Name> = glyph_names
+            .iter()
+            .filter(|name| !owners.contains_key(*name))
+            .cloned()
+            .collect();
+        if !unknown.is_empty() {
+            unknown.sort();
+            return Err(RemoveError::UnknownGlyphs(unknown));
+        }
 
-            let opentype_category = match record.3 {
-                Some(s) => s.parse().map_err(|e: &'static str| {
-                    LoadGlyphDataError::InvalidOpenTypeCategory(glyph_name.clone(), s, anyhow!(e))
-                })?,
-                None => OpenTypeCategory::default(),
-            };
+        if !force {
+            let dependents = self.component_dependents(glyph_names);
+            if !dependents.is_empty() {
+                return Err(RemoveError::UsedAsComponents(dependents));
+            }
+        }
 
-            glyph_data.insert(
-                glyph_name,
-                GlyphRecord {
-                    postscript_name: record.1,
-                    codepoints,
-                    opentype_category,
-                    export: record.4,
-                },
-            );
+        let groups_before = self.group_names();
+        for set in self.sets.values_mut() {
+            set.split_off(glyph_names);
+        }
+        self.prune_group_order();
+        self.glyph_order.retain(|name| !glyph_names.contains(name));
+        // Pairs with a group as the second side go away with the group's last
+        // member, too.
+        let groups_after = self.group_names();
+        let emptied_groups: HashSet<&Name> = groups_before.difference(&groups_after).collect();
+        for set in self.sets.values_mut() {
+            for source in set.sources.values_mut() {
+                for seconds in source.kerning.values_mut() {
+                    seconds.retain(|second, _| {
+                        !glyph_names.contains(second) && !emptied_groups.contains(second)
+                    });
+                }
+                source.kerning.retain(|_, seconds| !seconds.is_empty());
+            }
         }
+        self.place_group_kerning();
 
-        Ok(glyph_data)
+        Ok(())
     }
 
-    // NOTE: Use anyhow::Error here because we use anyhow's Context trait in main.
-    // Something about Sync and Send.
-    fn parse_codepoints(v: &str) -> Result<Codepoints, anyhow::Error> {
-        let mut codepoints = Vec::new();
-        let mut seen = HashSet::new();
+    /// The glyphs outside `glyph_names` that use any of them as components,
+    /// directly or through nested components, with the ones they use.
+    pub fn component_dependents(
+        &self,
+        glyph_names: &HashSet<Name>,
+    ) -> BTreeMap<Name, BTreeSet<Name>> {
+        let graph = self.component_graph();
+        let components_in_glyph = |name: Name| -> Vec<Name> {
+            graph
+                .get(&name)
+                .map(|bases| bases.iter().map(|base| (*base).clone()).collect())
+                .unwrap_or_default()
+        };
 
-        for codepoint in v.split_whitespace() {
-            let codepoint = u32::from_str_radix(codepoint, 16)?;
-            let codepoint = char::try_from(codepoint)?;
-            if seen.insert(codepoint) {
-                codepoints.push(codepoint);
+        let mut dependents = BTreeMap::new();
+        for name in graph.keys().filter(|name| !glyph_names.contains(**name)) {
+            let start = HashSet::from([(*name).clone()]);
+            let reachable = crate::util::glyphset_follow_composites(&start, components_in_glyph);
+            let used: BTreeSet<Name> = reachable.intersection(glyph_names).cloned().collect();
+            if !used.is_empty() {
+                dependents.insert((*name).clone(), used);
             }
         }
+        dependents
+    }
 
-        Ok(norad::Codepoints::new(codepoints))
+    /// The components used by each glyph in any set, source or layer.
+    fn component_graph(&self) -> HashMap<&Name, HashSet<&Name>> {
+        let mut components: HashMap<&Name, HashSet<&Name>> = HashMap::new();
+        for set in self.sets.values() {
+            for source in set.sources.values() {
+                for layer in source.layers.values() {
+                    for (name, glyph) in &layer.glyphs {
+                        for component in &glyph.components {
+                            components.entry(name).or_default().insert(&component.base);
+                        }
+                    }
+                }
+            }
+        }
+        components
     }
 
-    fn write_glyph_data(
-        glyph_data: &BTreeMap<Name, GlyphRecord>,
-        path: &Path,
-    ) -> Result<(), csv::Error> {
-        let mut writer = csv::Writer::from_path(&path)?;
-
-        writer.write_record(&[
-            "name",
-            "postscript_name",
-            "codepoints",
-            "opentype_category",
-            "export",
-        ])?;
+    /// The glyphs in `glyph_names` plus the glyphs used as components only by
+    /// them, directly or through nested components, in any source or layer.
+    fn with_exclusive_components(&self, glyph_names: &HashSet<Name>) -> HashSet<Name> {
+        let components = self.component_graph();
+        let mut users: HashMap<&Name, HashSet<&Name>> = HashMap::new();
+        for (name, bases) in &components {
+            for base in bases {
+                users.entry(base).or_default().insert(name);
+            }
+        }
 
-        for glyph_name in glyph_data.keys() {
-            let record = &glyph_data[glyph_name];
-            let codepoints_str: String = record
-                .codepoints
+        let mut result = glyph_names.clone();
+        loop {
+            let exclusive: HashSet<Name> = result
                 .iter()
-                .map(|c| format!("{:04X}", c as usize))
-                .collect::<Vec<_>>()
-                .join(" ");
-            writer.serialize((
-                glyph_name,
-                &record.postscript_name,
-                codepoints_str,
-                &record.opentype_category,
-                record.export,
-            ))?;
+                .filter_map(|name| components.get(name))
+                .flatten()
+                .filter(|base| !result.contains(**base))
+                .filter(|base| users[**base].iter().all(|user| result.contains(*user)))
+                .map(|base| (*base).clone())
+                .collect();
+            if exclusive.is_empty() {
+                break;
+            }
+            result.extend(exclusive);
         }
-        writer.flush()?;
+        result
+    }
 
+    /// Create an empty set.
+    pub fn new_set(&mut self, set_name: &Name) -> Result<(), SetError> {
+        if self.sets.contains_key(set_name) {
+            return Err(SetError::SetExists(set_name.clone()));
+        }
+        self.sets.insert(set_name.clone(), Set::default());
         Ok(())
     }
-}
 
-impl Default for Source {
-    fn default() -> Self {
-        let layer = Layer {
-            default: true,
-            ..Default::default()
-        };
-        Self {
-            layers: BTreeMap::from([(Name::new("public.default").unwrap(), layer)]),
+    /// Rename a set.
+    pub fn rename_set(&mut self, old: &Name, new: &Name) -> Result<(), SetError> {
+        if self.sets.contains_key(new) {
+            return Err(SetError::SetExists(new.clone()));
         }
+        let set = self
+            .sets
+            .remove(old)
+            .ok_or_else(|| SetError::UnknownSet(old.clone()))?;
+        self.sets.insert(new.clone(), set);
+        // The new name can change which set comes first.
+        self.place_group_kerning();
+        Ok(())
     }
-}
-
-impl Source {
-    pub(crate) fn from_path(path: &Path) -> Result<Self, LoadSourceError> {
-        let mut layers = BTreeMap::new();
-        let mut found_default = false;
-
-        for entry in std::fs::read_dir(path)? {
-            let entry = entry?;
-            let path = entry.path();
-            if let Some(file_name) = path.file_name() {
-                let metadata = entry.metadata()?;
-                if metadata.is_dir()
-                    && (file_name == "glyphs" || file_name.to_string_lossy().starts_with("glyphs."))
-                {
-                    let (layer, layerinfo) = Layer::from_path(&path)
-                        .map_err(|e| LoadSourceError::LoadLayer(path.clone(), e))?;
 
-                    // All non-default layer names start with a dot after "glyphs".
-                    // Hope that we don't bump into filesystem case-sensitivity
-                    // issues.
-                    if file_name == "glyphs" {
-                        found_default = true;
-                    }
-                    layers.insert(layerinfo.name, layer);
-                }
-            }
+    /// Merge the set `from` into the set `into` and remove it.
+    ///
+    /// Besides the glyphs, the feature code of `from` is appended to the one
+    /// of `into`, and its lib entries are added.
+    pub fn merge_sets(&mut self, from: &Name, into: &Name) -> Result<(), SetError> {
+        if from == into {
+            return Err(SetError::SameSet(from.clone()));
         }
-
-        if !found_default {
-            return Err(LoadSourceError::NoDefaultLayer);
+        if !self.sets.contains_key(into) {
+            return Err(SetError::UnknownSet(into.clone()));
+        }
+        let mut set = self
+            .sets
+            .remove(from)
+            .ok_or_else(|| SetError::UnknownSet(from.clone()))?;
+
+        let target = self.sets.get_mut(into).expect("checked above");
+        let features = std::mem::take(&mut set.features);
+        if !features.is_empty() {
+            if !target.features.is_empty() && !target.features.ends_with('\n') {
+                target.features.push('\n');
+            }
+            target.features.push_str(&features);
         }
+        target.lib.extend(std::mem::take(&mut set.lib));
+        target.merge(set);
+        self.place_group_kerning();
 
-        Ok(Source { layers })
+        Ok(())
     }
 
-    pub fn get_default_layer_mut(&mut self) -> &mut Layer {
-        self.layers
-            .values_mut()
-            .find(|layer| layer.default)
-            .unwrap()
+    /// Move the glyphs in `glyph_names` out of the set `set_name` into the new
+    /// set `new_set_name`, which keeps the metrics and anchors tables of the
+    /// original set.
+    pub fn split_set(
+        &mut self,
+        set_name: &Name,
+        new_set_name: &Name,
+        glyph_names: &HashSet<Name>,
+    ) -> Result<(), SetError> {
+        if self.sets.contains_key(new_set_name) {
+            return Err(SetError::SetExists(new_set_name.clone()));
+        }
+        let set = self
+            .sets
+            .get_mut(set_name)
+            .ok_or_else(|| SetError::UnknownSet(set_name.clone()))?;
+        let coverage = set.glyph_coverage();
+        let mut outside: Vec<Name> = glyph_names.difference(&coverage).cloned().collect();
+        if !outside.is_empty() {
+            outside.sort();
+            return Err(SetError::GlyphsNotInSet(set_name.clone(), outside));
+        }
+
+        let split = set.split_off(glyph_names);
+        self.sets.insert(new_set_name.clone(), split);
+        self.place_group_kerning();
+        Ok(())
     }
 
-    pub fn get_or_create_layer(&mut self, name: Name) -> &mut Layer {
-        self.layers.entry(name).or_default()
+    /// Delete a set. A set with glyphs can only be deleted by merging it into
+    /// the set `into`, see [`Self::merge_sets`].
+    pub fn delete_set(&mut self, set_name: &Name, into: Option<&Name>) -> Result<(), SetError> {
+        if let Some(into) = into {
+            return self.merge_sets(set_name, into);
+        }
+        let set = self
+            .sets
+            .get(set_name)
+            .ok_or_else(|| SetError::UnknownSet(set_name.clone()))?;
+        let glyph_count = set.glyph_coverage().len();
+        if glyph_count > 0 {
+            return Err(SetError::NotEmpty(set_name.clone(), glyph_count));
+        }
+        let has_lib = !set.lib.is_empty() || set.sources.values().any(|s| !s.lib.is_empty());
+        if !set.features.trim().is_empty() || has_lib {
+            return Err(SetError::HasFeaturesOrLib(set_name.clone()));
+        }
+        self.sets.remove(set_name);
+        Ok(())
     }
 
-    pub fn new_with_default_layer_name(name: Name) -> Self {
-        let layer = Layer {
-            default: true,
+    /// Add a source, importing the glyphs of all sets from `font`.
+    ///
+    /// Glyphs missing from `font` are left out, glyphs not yet in any set
+    /// are not imported.
+    pub fn add_source(
+        &mut self,
+        font: &norad::Font,
+        source_name: &Name,
+        location: Location,
+    ) -> Result<ImportReport, SourceError> {
+        if self.sources.contains_key(source_name) {
+            return Err(SourceError::SourceExists(source_name.clone()));
+        }
+        self.check_location(&location)?;
+
+        // Glyphs missing from the default layer of the UFO would lose their
+        // glyph data, so they are skipped even if other layers have them.
+        let mut glyph_names = HashSet::new();
+        let mut skipped_glyphs = Vec::new();
+        for name in self.glyph_owners().into_keys() {
+            if font.default_layer().contains_glyph(&name) {
+                glyph_names.insert(name);
+            } else if font.iter_layers().any(|layer| layer.contains_glyph(&name)) {
+                skipped_glyphs.push(name);
+            }
+        }
+        skipped_glyphs.sort();
+
+        // Import in one go, as a failing import changes nothing and so leaves
+        // no trace of the source. Only glyphs that are already in some set
+        // are imported, and they are routed into their sets, so no glyph ever
+        // ends up in the fallback set.
+        let options = ImportOptions {
+            mode: ImportMode::UpdateExisting,
             ..Default::default()
         };
-        Self {
-            layers: BTreeMap::from([(name, layer)]),
+        let unused_set_name = Name::new("unused").expect("valid name");
+        let mut report =
+            self.import_with_options(font, &glyph_names, &unused_set_name, source_name, &options)?;
+        report.skipped_glyphs = skipped_glyphs;
+        self.sources
+            .get_mut(source_name)
+            .expect("the import registers the source")
+            .location = location;
+
+        Ok(report)
+    }
+
+    /// Check that all axes of `location` are in the design space.
+    fn check_location(&self, location: &Location) -> Result<(), SourceError> {
+        for axis_name in location.keys() {
+            if !self.designspace.axes.iter().any(|a| a.name == *axis_name) {
+                return Err(SourceError::UnknownAxis(axis_name.clone()));
+            }
         }
+        Ok(())
     }
 
-    pub(crate) fn save(&self, source_name: &str, set_path: &Path) -> Result<(), SaveSourceError> {
-        let source_path = set_path.join(format!("source.{source_name}"));
-        std::fs::create_dir(&source_path).map_err(SaveSourceError::CreateDir)?;
+    /// Rename a source in the registry, the font info, all sets and the
+    /// layer sources of the design space. The file name it is exported to
+    /// stays the same.
+    pub fn rename_source(&mut self, old: &Name, new: &Name) -> Result<(), SourceError> {
+        if self.sources.contains_key(new) {
+            return Err(SourceError::SourceExists(new.clone()));
+        }
+        let record = self
+            .sources
+            .remove(old)
+            .ok_or_else(|| SourceError::UnknownSource(old.clone()))?;
+        self.sources.insert(new.clone(), record);
+
+        if let Some(font_info) = self.font_info.remove(old) {
+            self.font_info.insert(new.clone(), font_info);
+        }
+        for set in self.sets.values_mut() {
+            if let Some(source) = set.sources.remove(old) {
+                set.sources.insert(new.clone(), source);
+            }
+        }
+        for layer_source in self.designspace.layer_sources.values_mut() {
+            if layer_source.source == *old {
+                layer_source.source = new.clone();
+            }
+        }
 
-        let mut existing_layer_names = HashSet::new();
-        for (layer_name, layer) in &self.layers {
-            layer
-                .save(layer_name, &source_path, &mut existing_layer_names)
-                .map_err(|e| SaveSourceError::SaveLayer(layer_name.clone(), e))?;
+        Ok(())
+    }
+
+    /// Remove a source and everything stored for it.
+    pub fn remove_source(&mut self, source_name: &Name) -> Result<(), SourceError> {
+        if self.sources.remove(source_name).is_none() {
+            return Err(SourceError::UnknownSource(source_name.clone()));
+        }
+        self.font_info.remove(source_name);
+        for set in self.sets.values_mut() {
+            set.sources.remove(source_name);
         }
+        self.designspace
+            .layer_sources
+            .retain(|_, layer_source| layer_source.source != *source_name);
 
         Ok(())
     }
-}
 
-impl Layer {
-    pub(crate) fn from_path(path: &Path) -> Result<(Self, LayerInfo), LoadLayerError> {
-        let mut glyphs = BTreeMap::new();
-        let color_marks = Self::load_color_marks(&path.join("color_marks.csv"))
-            .map_err(LoadLayerError::LoadColorMarks)?;
-        let layerinfo: LayerInfo = plist::from_file(path.join("layerinfo.plist"))
-            .map_err(LoadLayerError::LoadLayerInfo)?;
+    /// Copy a source under a new name, e.g. as the starting point of a new
+    /// master. The copy is exported to a UFO named after it.
+    ///
+    /// The copy is placed at the location of the original, updated with
+    /// `location`. If the design space has axes, that must be a different
+    /// location. The layer sources of the original are copied, too, and
+    /// move along with the master.
+    pub fn duplicate_source(
+        &mut self,
+        source_name: &Name,
+        new: &Name,
+        location: Location,
+    ) -> Result<(), SourceError> {
+        if self.sources.contains_key(new) {
+            return Err(SourceError::SourceExists(new.clone()));
+        }
+        self.check_location(&location)?;
+        let mut record = self
+            .sources
+            .get(source_name)
+            .ok_or_else(|| SourceError::UnknownSource(source_name.clone()))?
+            .clone();
+        let old_location = record.location.clone();
+        record.location.extend(location);
+        if !self.designspace.axes.is_empty() && record.location == old_location {
+            return Err(SourceError::SameLocation(new.clone(), source_name.clone()));
+        }
+        record.filename = new.to_string();
+
+        let layer_sources: Vec<(Name, LayerSource)> = self
+            .designspace
+            .layer_sources
+            .values()
+            .filter(|layer_source| layer_source.source == *source_name)
+            .map(|layer_source| {
+                let mut layer_source = layer_source.clone();
+                layer_source.source = new.clone();
+                for (axis_name, value) in layer_source.location.iter_mut() {
+                    if let (Some(old_value), Some(new_value)) =
+                        (old_location.get(axis_name), record.location.get(axis_name))
+                    {
+                        *value += new_value - old_value;
+                    }
+                }
+                let name = Name::new(&format!("{new}.{}", layer_source.layer))
+                    .expect("made of valid names");
+                (name, layer_source)
+            })
+            .collect();
+        if let Some((name, _)) = layer_sources
+            .iter()
+            .find(|(name, _)| self.designspace.layer_sources.contains_key(name))
+        {
+            return Err(SourceError::LayerSourceExists(name.clone()));
+        }
+        self.designspace.layer_sources.extend(layer_sources);
+        self.sources.insert(new.clone(), record);
 
-        for entry in std::fs::read_dir(path)? {
-            let path = entry?.path();
-            if path.is_file() && path.extension().map_or(false, |n| n == "glif") {
-                let glif = norad::Glyph::load(&path)
-                    .map_err(|e| LoadLayerError::LoadGlyph(path.clone(), e))?;
-                glyphs.insert(glif.name().clone(), glif);
+        if let Some(font_info) = self.font_info.get(source_name) {
+            self.font_info.insert(new.clone(), font_info.clone());
+        }
+        for set in self.sets.values_mut() {
+            if let Some(source) = set.sources.get(source_name) {
+                set.sources.insert(new.clone(), source.clone());
             }
         }
 
-        Ok((
-            Layer {
-                glyphs,
-                color_marks,
-                default: path.file_name() == Some(OsStr::new("glyphs")),
-            },
-            layerinfo,
-        ))
+        Ok(())
     }
 
-    pub(crate) fn from_ufo_layer(layer: &norad::Layer, glyph_names: &HashSet<Name>) -> Self {
-        let mut glyphs = BTreeMap::new();
-        let mut color_marks = BTreeMap::new();
+    fn assemble_sources(&self, source_names: &HashSet<Name>) -> HashMap<Name, Source> {
+        let mut assembled_sources: HashMap<Name, Source> = HashMap::new();
 
-        for glyph in layer
-            .iter()
-            .filter(|g| glyph_names.contains(g.name().as_str()))
-        {
-            let mut our_glyph = glyph.clone();
-            if let Some(color_string) = our_glyph.lib.remove("public.markColor") {
-                // FIXME: We roundtrip color here so that we round up front to
-                // make roundtrip equality testing easier.
-                let our_color = Color::from_str(color_string.as_string().unwrap()).unwrap();
-                let our_color = Color::from_str(&our_color.to_rgba_string()).unwrap();
-                color_marks.insert(glyph.name().clone(), our_color);
+        for set in self.sets.values() {
+            for (source_name, source) in set
+                .sources
+                .iter()
+                .filter(|(name, _)| source_names.contains(*name))
+            {
+                // NOTE: The default layer names of a source agree across sets,
+                // as checked on load and import.
+                let assembled_source =
+                    assembled_sources
+                        .entry(source_name.clone())
+                        .or_insert_with(|| {
+                            Source::new_with_default_layer_name(source.default_layer_name().clone())
+                        });
+                for (layer_name, layer) in source.layers.iter() {
+                    let assembled_layer = assembled_source
+                        .layers
+                        .entry(layer_name.clone())
+                        .or_default();
+                    assembled_layer.glyphs.extend(layer.glyphs.clone());
+                    assembled_layer
+                        .color_marks
+                        .extend(layer.color_marks.clone());
+                    assembled_layer.metrics.extend(layer.metrics.clone());
+                    assembled_layer.anchors.extend(layer.anchors.clone());
+                    assembled_layer.encoded.extend(layer.encoded.clone());
+                    if layer.color.is_some() {
+                        assembled_layer.color = layer.color.clone();
+                    }
+                    assembled_layer.lib.extend(layer.lib.clone());
+                    assembled_layer.default = layer.default;
+                }
+                for (first, seconds) in source.kerning.iter() {
+                    assembled_source
+                        .kerning
+                        .entry(first.clone())
+                        .or_default()
+                        .extend(seconds.iter().map(|(k, v)| (k.clone(), *v)));
+                }
             }
-            // TODO: split out the codepoints.
-            glyphs.insert(glyph.name().clone(), our_glyph);
         }
 
-        Self {
-            glyphs,
-            color_marks,
-            default: false,
-        }
+        assembled_sources
     }
 
-    pub(crate) fn into_ufo_layer(self, ufo_layer: &mut norad::Layer) {
-        for (name, mut glyph) in self.glyphs {
-            if let Some(c) = self.color_marks.get(&name) {
-                glyph
-                    .lib
-                    .insert("public.markColor".into(), c.to_rgba_string().into());
+    /// Merge the groups of all sets, keeping only the members in
+    /// `glyph_names`. Groups left empty are dropped.
+    fn assemble_groups(&self, glyph_names: &HashSet<Name>) -> norad::Groups {
+        let mut assembled_groups = norad::Groups::new();
+
+        for set in self.sets.values() {
+            for (group_name, members) in &set.groups {
+                let members = members.iter().filter(|name| glyph_names.contains(*name));
+                assembled_groups
+                    .entry(group_name.clone())
+                    .or_default()
+                    .extend(members.cloned());
+            }
+        }
+        assembled_groups.retain(|_, members| !members.is_empty());
+
+        // Members are in set order so far, restore the UFO order. Members
+        // without a recorded position go last.
+        for (group_name, members) in assembled_groups.iter_mut() {
+            if let Some(group_order) = self.group_order.get(group_name) {
+                members.sort_by_key(|name| {
+                    group_order
+                        .iter()
+                        .position(|n| n == name)
+                        .unwrap_or(usize::MAX)
+                });
             }
-            ufo_layer.insert_glyph(glyph);
         }
+
+        assembled_groups
     }
 
-    // NOTE: Keep in sync with into_ufo_layer.
-    // TODO: Make a proper to-norad export API and dogfood it.
-    #[cfg(test)]
-    pub(crate) fn export_glyph(&self, name: &str) -> norad::Glyph {
-        let mut glyph = self.glyphs[name].clone();
-        if let Some(c) = self.color_marks.get(name) {
-            glyph
-                .lib
-                .insert("public.markColor".into(), c.to_rgba_string().into());
+    /// Reconstruct the `public.glyphOrder`, `public.postscriptNames`,
+    /// `public.openTypeCategories` and `public.skipExportGlyphs` lib keys from
+    /// the glyph data of all sets, restricted to `glyph_names`.
+    ///
+    /// The glyph order follows the recorded UFO glyph order. Glyphs without
+    /// a recorded position go last, in set order, then glyph name order.
+    fn assemble_glyph_data_lib(&self, glyph_names: &HashSet<Name>) -> norad::Plist {
+        let positions: HashMap<&Name, usize> = self
+            .glyph_order
+            .iter()
+            .enumerate()
+            .map(|(position, name)| (name, position))
+            .collect();
+        let mut glyph_order = Vec::new();
+        let mut postscript_names = norad::Plist::new();
+        let mut opentype_categories = norad::Plist::new();
+        let mut skip_exports = Vec::new();
+
+        for set in self.sets.values() {
+            for (name, record) in set
+                .glyph_data
+                .iter()
+                .filter(|(name, _)| glyph_names.contains(*name))
+            {
+                glyph_order.push((positions.get(name).copied(), name));
+                if let Some(postscript_name) = &record.postscript_name {
+                    postscript_names.insert(name.to_string(), postscript_name.clone().into());
+                }
+                if record.opentype_category != OpenTypeCategory::Unassigned {
+                    opentype_categories.insert(
+                        name.to_string(),
+                        record.opentype_category.as_ufo_str().into(),
+                    );
+                }
+                if !record.export {
+                    skip_exports.push(plist::Value::String(name.to_string()));
+                }
+            }
         }
-        glyph
+
+        glyph_order.sort_by_key(|(order, _)| (order.is_none(), *order));
+        let glyph_order: Vec<plist::Value> = glyph_order
+            .into_iter()
+            .map(|(_, name)| plist::Value::String(name.to_string()))
+            .collect();
+
+        let mut lib = norad::Plist::new();
+        if !glyph_order.is_empty() {
+            lib.insert("public.glyphOrder".into(), glyph_order.into());
+        }
+        if !postscript_names.is_empty() {
+            lib.insert("public.postscriptNames".into(), postscript_names.into());
+        }
+        if !opentype_categories.is_empty() {
+            lib.insert(
+                "public.openTypeCategories".into(),
+                opentype_categories.into(),
+            );
+        }
+        if !skip_exports.is_empty() {
+            lib.insert("public.skipExportGlyphs".into(), skip_exports.into());
+        }
+
+        lib
     }
 
-    fn load_color_marks(path: &Path) -> Result<BTreeMap<Name, Color>, csv::Error> {
-        let mut color_marks = BTreeMap::new();
+    /// Merge the libs of all sets that contain some of `glyph_names`, with
+    /// the values specific to `source_name`.
+    fn assemble_set_lib(&self, glyph_names: &HashSet<Name>, source_name: &Name) -> norad::Plist {
+        let mut lib = norad::Plist::new();
 
-        if !path.exists() {
-            return Ok(color_marks);
+        for set in self.sets.values() {
+            if set.glyph_coverage().is_disjoint(glyph_names) {
+                continue;
+            }
+            lib.extend(set.lib.clone());
+            if let Some(source) = set.sources.get(source_name) {
+                lib.extend(source.lib.clone());
+            }
         }
 
-        let mut reader = csv::Reader::from_path(&path)?;
-        for result in reader.deserialize() {
-            let record: (Name, Color) = result?;
-            color_marks.insert(record.0, record.1);
+        lib
+    }
+
+    /// Concatenate the feature code prologue and the feature code of all
+    /// sets containing glyphs in `glyph_names`, in set name order.
+    fn assemble_features(&self, glyph_names: &HashSet<Name>) -> String {
+        let mut features = self.features.clone();
+
+        for set in self.sets.values() {
+            if set.features.is_empty() || set.glyph_coverage().is_disjoint(glyph_names) {
+                continue;
+            }
+            if !features.is_empty() && !features.ends_with('\n') {
+                features.push('\n');
+            }
+            features.push_str(&set.features);
         }
 
-        Ok(color_marks)
+        features
     }
 
-    pub(crate) fn save(
+    pub fn export(
         &self,
-        layer_name: &Name,
-        source_path: &Path,
-        existing_layer_names: &mut HashSet<String>,
-    ) -> Result<(), SaveLayerError> {
-        if self.glyphs.is_empty() {
+        glyph_names: &HashSet<Name>,
+        source_names: &HashSet<Name>,
+    ) -> Result<BTreeMap<Name, norad::Font>, ExportError> {
+        let mut ufos: BTreeMap<Name, norad::Font> = BTreeMap::new();
+
+        // First, make a copy of self and resolve the glyph list to include all
+        // referenced components. Make the union set up front across all layers
+        // because not every layer by itself contains all glyphs.
+        //
+        // TODO: Be smarter by resolving from the Fontgarden instead of making a
+        // copy of self and then pruning it?
+        let mut sources = self.assemble_sources(source_names);
+        let mut glyph_names = glyph_names.clone();
+        for source in sources.values() {
+            for layer in source.layers.values() {
+                let components_in_glyph = |n| {
+                    layer
+                        .glyphs
+                        .get(&n)
+                        .map(|g| g.components.iter().map(|c| c.base.clone()).collect())
+                        .unwrap_or_default()
+                };
+
+                glyph_names.extend(crate::util::glyphset_follow_composites(
+                    &glyph_names,
+                    components_in_glyph,
+                ));
+            }
+        }
+
+        // Next, prune sources, glyphs and groups not in the resolved glyph
+        // list.
+        let groups = self.assemble_groups(&glyph_names);
+        let features = self.assemble_features(&glyph_names);
+        let glyph_data_lib = self.assemble_glyph_data_lib(&glyph_names);
+        let glyph_data: BTreeMap<Name, GlyphRecord> = self
+            .sets
+            .values()
+            .flat_map(|set| set.glyph_data.clone())
+            .collect();
+        for source in sources.values_mut() {
+            for layer in source.layers.values_mut() {
+                layer.glyphs.retain(|name, _| glyph_names.contains(name));
+                layer
+                    .color_marks
+                    .retain(|name, _| glyph_names.contains(name));
+                layer.metrics.retain(|name, _| glyph_names.contains(name));
+                layer.anchors.retain(|name, _| glyph_names.contains(name));
+                layer.encoded.retain(|name| glyph_names.contains(name));
+            }
+
+            let side_exported =
+                |side: &Name| glyph_names.contains(side) || groups.contains_key(side);
+            source.kerning.retain(|first, seconds| {
+                seconds.retain(|second, _| side_exported(second));
+                side_exported(first) && !seconds.is_empty()
+            });
+        }
+
+        // Then, transform the pruned tree into UFO structures.
+        let owners = self.glyph_owners();
+        for (source_name, mut source) in sources {
+            let ufo = ufos.entry(source_name.clone()).or_default();
+            ufo.groups = groups.clone();
+            ufo.features = features.clone();
+            ufo.lib.extend(self.lib.clone());
+            if let Some(record) = self.sources.get(&source_name) {
+                ufo.lib.extend(record.lib.clone());
+                for (path, data) in &record.data {
+                    ufo.data
+                        .insert(path.clone(), data.clone())
+                        .map_err(|e| ExportError::Other(e.into()))?;
+                }
+            }
+            ufo.lib
+                .extend(self.assemble_set_lib(&glyph_names, &source_name));
+            ufo.lib.extend(glyph_data_lib.clone());
+            if let Some(font_info) = self.font_info.get(&source_name) {
+                ufo.font_info = font_info.clone();
+            }
+            ufo.kerning = source.kerning;
+
+            // A UFO has one image per file name, but images of different sets
+            // can share one. Those found later get a numbered file name.
+            let mut image_hashes: BTreeMap<PathBuf, &String> = BTreeMap::new();
+            let mut image_file_names: HashMap<(PathBuf, &String), PathBuf> = HashMap::new();
+            for layer in source.layers.values_mut() {
+                let images = layer
+                    .glyphs
+                    .iter_mut()
+                    .filter_map(|(name, glyph)| Some((name, glyph.image.as_mut()?)));
+                for (glyph_name, image) in images {
+                    let hash = owners
+                        .get(glyph_name)
+                        .and_then(|set_name| self.sets[set_name].sources.get(&source_name))
+                        .and_then(|source| {
+                            source
+                                .images
+                                .get(image.file_name.to_string_lossy().as_ref())
+                        });
+                    let hash = match hash {
+                        Some(hash) => hash,
+                        None => continue,
+                    };
+                    let file_name = image_file_names
+                        .entry((image.file_name.clone(), hash))
+                        .or_insert_with(|| {
+                            let mut file_name = image.file_name.clone();
+                            let mut number = 1;
+                            while matches!(image_hashes.get(&file_name), Some(h) if *h != hash) {
+                                number += 1;
+                                file_name =
+                                    crate::util::numbered_file_name(&image.file_name, number);
+                            }
+                            image_hashes.insert(file_name.clone(), hash);
+                            file_name
+                        });
+                    image.file_name = file_name.clone();
+                }
+            }
+
+            // The default layer comes first, then the recorded layer order.
+            // Layers without a recorded position go last, by name.
+            let layer_order = self
+                .sources
+                .get(&source_name)
+                .map(|record| record.layer_order.as_slice())
+                .unwrap_or_default();
+            let mut layers: Vec<(Name, Layer)> = source.layers.into_iter().collect();
+            layers.sort_by_key(|(name, layer)| {
+                let position = layer_order.iter().position(|n| n == name);
+                (!layer.default, position.unwrap_or(usize::MAX))
+            });
+
+            for (layer_name, layer) in layers {
+                if layer.glyphs.is_empty() {
+                    continue;
+                }
+
+                if layer.default {
+                    {
+                        let ufo_layer = ufo.layers.default_layer_mut();
+                        layer.into_ufo_layer(ufo_layer, &glyph_data);
+                    }
+                    if layer_name != *ufo.layers.default_layer_mut().name() {
+                        ufo.layers
+                            .rename_layer(
+                                &ufo.layers.default_layer().name().clone(),
+                                &layer_name,
+                                false,
+                            )
+                            .unwrap();
+                    }
+                } else {
+                    let ufo_layer = match ufo.layers.get_mut(&layer_name) {
+                        Some(ufo_layer) => ufo_layer,
+                        None => ufo
+                            .layers
+                            .new_layer(&layer_name)
+                            .expect("can't make new layer"),
+                    };
+                    layer.into_ufo_layer(ufo_layer, &glyph_data);
+                }
+            }
+
+            // Finally, add the images referenced by exported glyphs.
+            for (file_name, hash) in image_hashes {
+                if let Some(data) = self.images.get(hash) {
+                    ufo.images
+                        .insert(file_name, data.clone())
+                        .map_err(|e| ExportError::Other(e.into()))?;
+                }
+            }
+        }
+
+        Ok(ufos)
+    }
+
+    /// Load the font_info.csv table, with one row per font info field and one
+    /// column per source.
+    fn load_font_info(path: &Path) -> Result<BTreeMap<Name, norad::FontInfo>, LoadFontInfoError> {
+        let mut reader = csv::Reader::from_path(path).map_err(LoadFontInfoError::Csv)?;
+
+        let headers = reader.headers().map_err(LoadFontInfoError::Csv)?.clone();
+        let source_names = headers
+            .iter()
+            .skip(2)
+            .map(|s| Name::new(s).map_err(|e| LoadFontInfoError::InvalidSourceName(s.into(), e)))
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut values: Vec<plist::Dictionary> = vec![plist::Dictionary::new(); source_names.len()];
+
+        for result in reader.records() {
+            let record = result.map_err(LoadFontInfoError::Csv)?;
+            let field = record.get(0).unwrap_or_default();
+            let kind = record.get(1).unwrap_or_default();
+            let kind: FontInfoValueKind = kind.parse().map_err(|e: &'static str| {
+                LoadFontInfoError::InvalidValueKind(field.into(), kind.into(), anyhow!(e))
+            })?;
+
+            for ((source_name, source_values), v) in source_names
+                .iter()
+                .zip(values.iter_mut())
+                .zip(record.iter().skip(2))
+            {
+                if v.is_empty() {
+                    continue;
+                }
+                let value = kind.decode(v).map_err(|e| {
+                    LoadFontInfoError::InvalidValue(field.into(), source_name.clone(), v.into(), e)
+                })?;
+                source_values.insert(field.into(), value);
+            }
+        }
+
+        let mut font_info = BTreeMap::new();
+        for (source_name, source_values) in source_names.into_iter().zip(values) {
+            // plist 1.4 cannot deserialize from a Value directly, so go through
+            // an XML buffer.
+            let mut buffer = Vec::new();
+            let info = plist::Value::Dictionary(source_values)
+                .to_writer_xml(&mut buffer)
+                .and_then(|_| plist::from_bytes(&buffer))
+                .map_err(|e| LoadFontInfoError::Convert(source_name.clone(), e))?;
+            font_info.insert(source_name, info);
+        }
+
+        Ok(font_info)
+    }
+
+    fn write_font_info(
+        font_info: &BTreeMap<Name, norad::FontInfo>,
+        path: &Path,
+    ) -> Result<(), WriteFontInfoError> {
+        let mut values = Vec::new();
+        for (source_name, info) in font_info {
+            let mut buffer = Vec::new();
+            let value = plist::to_writer_xml(&mut buffer, info)
+                .and_then(|_| plist::Value::from_reader_xml(buffer.as_slice()))
+                .map_err(|e| WriteFontInfoError::Convert(source_name.clone(), e))?;
+            values.push(value.into_dictionary().unwrap_or_default());
+        }
+        let fields: BTreeSet<&String> = values.iter().flat_map(|v| v.keys()).collect();
+
+        let mut writer = csv::Writer::from_path(path).map_err(WriteFontInfoError::Csv)?;
+
+        let mut headers = vec!["field", "type"];
+        headers.extend(font_info.keys().map(|name| name.as_str()));
+        writer
+            .write_record(&headers)
+            .map_err(WriteFontInfoError::Csv)?;
+
+        for field in fields {
+            // All values of a field must be of the same kind, else fall back
+            // to property lists for the whole row.
+            let mut kinds = values
+                .iter()
+                .filter_map(|v| v.get(field))
+                .map(FontInfoValueKind::of);
+            let first_kind = kinds.next().unwrap_or(FontInfoValueKind::Plist);
+            let kind = if kinds.all(|kind| kind == first_kind) {
+                first_kind
+            } else {
+                FontInfoValueKind::Plist
+            };
+
+            let mut record = vec![field.clone(), kind.as_str().to_string()];
+            for (source_name, source_values) in font_info.keys().zip(&values) {
+                let cell = match source_values.get(field) {
+                    Some(value) => kind
+                        .encode(value)
+                        .map_err(|e| WriteFontInfoError::Convert(source_name.clone(), e))?,
+                    None => String::new(),
+                };
+                record.push(cell);
+            }
+            writer
+                .write_record(&record)
+                .map_err(WriteFontInfoError::Csv)?;
+        }
+        writer
+            .flush()
+            .map_err(|e| WriteFontInfoError::Csv(e.into()))?;
+
+        Ok(())
+    }
+
+    fn load_sources(path: &Path) -> Result<BTreeMap<Name, SourceRecord>, LoadDesignspaceDataError> {
+        let mut sources = BTreeMap::new();
+
+        for (record, location) in crate::designspace::load_location_table(path, 4)? {
+            let mut record = record.into_iter();
+            let name = crate::designspace::parse_name(record.next().unwrap())?;
+            let filename = record.next().unwrap();
+            let italic = record.next().unwrap();
+            let italic = italic
+                .parse()
+                .map_err(|_| LoadDesignspaceDataError::InvalidItalicFlag(name.clone(), italic))?;
+            let default_layer = crate::designspace::parse_name(record.next().unwrap())?;
+            // The layer order, lib and data are filled in from layer_order.csv
+            // and the sources directory.
+            let record = SourceRecord {
+                filename,
+                location,
+                italic,
+                default_layer,
+                ..SourceRecord::new(&name)
+            };
+            sources.insert(name, record);
+        }
+
+        Ok(sources)
+    }
+
+    fn write_sources(&self, path: &Path) -> Result<(), csv::Error> {
+        let rows = self.sources.iter().map(|(name, record)| {
+            (
+                vec![
+                    name.to_string(),
+                    record.filename.clone(),
+                    record.italic.to_string(),
+                    record.default_layer.to_string(),
+                ],
+                &record.location,
+            )
+        });
+        self.designspace.write_location_table(
+            path,
+            &["name", "filename", "italic", "default_layer"],
+            rows,
+        )
+    }
+
+    /// Load the layer_order.csv table, listing the non-default layers of each
+    /// source in UFO order.
+    fn load_layer_order(path: &Path) -> Result<BTreeMap<Name, Vec<Name>>, csv::Error> {
+        let mut layer_order: BTreeMap<Name, Vec<Name>> = BTreeMap::new();
+
+        let mut reader = csv::Reader::from_path(path)?;
+        for result in reader.deserialize() {
+            let (source_name, layer_name): (Name, Name) = result?;
+            layer_order.entry(source_name).or_default().push(layer_name);
+        }
+
+        Ok(layer_order)
+    }
+
+    fn write_layer_order(&self, path: &Path) -> Result<(), csv::Error> {
+        let mut writer = csv::Writer::from_path(path)?;
+
+        writer.write_record(["source", "layer"])?;
+        for (source_name, record) in &self.sources {
+            for layer_name in &record.layer_order {
+                writer.serialize((source_name, layer_name))?;
+            }
+        }
+        writer.flush()?;
+
+        Ok(())
+    }
+
+    /// Load the group_order.csv table, listing the members of each group in
+    /// UFO order.
+    fn load_group_order(path: &Path) -> Result<BTreeMap<Name, Vec<Name>>, csv::Error> {
+        let mut group_order: BTreeMap<Name, Vec<Name>> = BTreeMap::new();
+
+        let mut reader = csv::Reader::from_path(path)?;
+        for result in reader.deserialize() {
+            let (group_name, glyph_name): (Name, Name) = result?;
+            group_order.entry(group_name).or_default().push(glyph_name);
+        }
+
+        Ok(group_order)
+    }
+
+    /// Load the glyph_order.csv table, listing glyph names in UFO order.
+    fn load_glyph_order(path: &Path) -> Result<Vec<Name>, csv::Error> {
+        let mut glyph_order = Vec::new();
+
+        let mut reader = csv::Reader::from_path(path)?;
+        for result in reader.deserialize() {
+            let (glyph_name,): (Name,) = result?;
+            glyph_order.push(glyph_name);
+        }
+
+        Ok(glyph_order)
+    }
+
+    fn write_glyph_order(&self, path: &Path) -> Result<(), csv::Error> {
+        let mut writer = csv::Writer::from_path(path)?;
+
+        writer.write_record(["name"])?;
+        for glyph_name in &self.glyph_order {
+            writer.serialize((glyph_name,))?;
+        }
+        writer.flush()?;
+
+        Ok(())
+    }
+
+    fn write_group_order(&self, path: &Path) -> Result<(), csv::Error> {
+        let mut writer = csv::Writer::from_path(path)?;
+
+        writer.write_record(["group", "glyph"])?;
+        for (group_name, members) in &self.group_order {
+            for glyph_name in members {
+                writer.serialize((group_name, glyph_name))?;
+            }
+        }
+        writer.flush()?;
+
+        Ok(())
+    }
+}
+
+impl SourceRecord {
+    /// A source exported into `<source name>.ufo`, at the default location.
+    pub fn new(source_name: &Name) -> Self {
+        Self {
+            filename: source_name.to_string(),
+            location: Location::new(),
+            italic: false,
+            default_layer: Name::new("public.default").unwrap(),
+            layer_order: Vec::new(),
+            lib: norad::Plist::new(),
+            data: BTreeMap::new(),
+        }
+    }
+
+    fn load_lib_and_data(&mut self, path: &Path) -> Result<(), LoadSourceDataError> {
+        let lib_path = path.join("lib.plist");
+        if lib_path.exists() {
+            self.lib = plist::from_file(lib_path).map_err(LoadSourceDataError::LoadLib)?;
+        }
+
+        let data_path = path.join("data");
+        let mut dirs = vec![data_path.clone()];
+        while let Some(dir) = dirs.pop() {
+            if !dir.is_dir() {
+                continue;
+            }
+            for entry in std::fs::read_dir(dir)? {
+                let path = entry?.path();
+                if path.is_dir() {
+                    dirs.push(path);
+                } else {
+                    let relative_path = path.strip_prefix(&data_path).unwrap().to_path_buf();
+                    self.data.insert(relative_path, std::fs::read(&path)?);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn save_lib_and_data(&self, path: &Path) -> Result<(), SaveSourceDataError> {
+        if self.lib.is_empty() && self.data.is_empty() {
             return Ok(());
         }
+        std::fs::create_dir_all(path).map_err(SaveSourceDataError::CreateDir)?;
+
+        if !self.lib.is_empty() {
+            plist::to_file_xml(path.join("lib.plist"), &self.lib)
+                .map_err(SaveSourceDataError::WriteLib)?;
+        }
+
+        for (relative_path, data) in &self.data {
+            let file_path = path.join("data").join(relative_path);
+            if let Some(parent) = file_path.parent() {
+                std::fs::create_dir_all(parent)
+                    .map_err(|e| SaveSourceDataError::WriteData(relative_path.clone(), e))?;
+            }
+            std::fs::write(&file_path, data)
+                .map_err(|e| SaveSourceDataError::WriteData(relative_path.clone(), e))?;
+        }
+
+        Ok(())
+    }
+
+    /// Take over the layer order of `font`. Layers known from previous
+    /// imports but missing from `font` go last.
+    fn update_layer_order(&mut self, font: &norad::Font) {
+        let mut layer_order: Vec<Name> = font
+            .iter_layers()
+            .filter(|layer| layer.name() != font.default_layer().name())
+            .map(|layer| layer.name().clone())
+            .collect();
+        for layer_name in &self.layer_order {
+            if !layer_order.contains(layer_name) {
+                layer_order.push(layer_name.clone());
+            }
+        }
+        self.layer_order = layer_order;
+    }
+
+    /// A source taking its metadata from the UFO it is first imported from.
+    fn from_font(font: &norad::Font, source_name: &Name) -> Self {
+        let info = &font.font_info;
+        let italic = info.italic_angle.is_some_and(|angle| angle != 0.0)
+            || matches!(
+                info.style_map_style_name,
+                Some(norad::fontinfo::StyleMapStyle::Italic)
+                    | Some(norad::fontinfo::StyleMapStyle::BoldItalic)
+            );
+        Self {
+            italic,
+            default_layer: font.default_layer().name().clone(),
+            ..Self::new(source_name)
+        }
+    }
+}
+
+impl Set {
+    fn from_path(path: &Path) -> Result<Self, LoadSetError> {
+        let glyph_data = Self::load_glyph_data(&path.join("glyph_data.csv"))
+            .map_err(LoadSetError::LoadGlyphData)?;
+        let groups =
+            Self::load_groups(&path.join("groups.csv")).map_err(LoadSetError::LoadGroups)?;
+        let features_path = path.join("features.fea");
+        let features = if features_path.exists() {
+            std::fs::read_to_string(features_path)?
+        } else {
+            String::new()
+        };
+        let lib_path = path.join("lib.plist");
+        let lib = if lib_path.exists() {
+            plist::from_file(lib_path).map_err(LoadSetError::LoadLib)?
+        } else {
+            norad::Plist::new()
+        };
+        let shared_glyphs = Self::load_shared_glyphs(&path.join("shared_glyphs"))?;
+
+        let mut sources = BTreeMap::new();
+        for entry in std::fs::read_dir(path)? {
+            let entry = entry?;
+            let path = entry.path();
+            let metadata = entry.metadata()?;
+            if metadata.is_dir() {
+                // TODO: Figure out when this call is None and if we should deal
+                // with it.
+                if let Some(file_name) = path.file_name() {
+                    if let Some(source_name) = file_name.to_string_lossy().strip_prefix("source.") {
+                        let source_name = Name::new(source_name)
+                            .map_err(|e| LoadSetError::NamingError(source_name.into(), e))?;
+                        let source = Source::from_path(&path, &shared_glyphs)
+                            .map_err(|e| LoadSetError::LoadSource(source_name.clone(), e))?;
+                        sources.insert(source_name, source);
+                    }
+                }
+            }
+        }
+
+        let metrics_path = path.join("metrics.csv");
+        let metrics = metrics_path.exists();
+        if metrics {
+            let mut table = Self::load_metrics(&metrics_path).map_err(LoadSetError::LoadMetrics)?;
+            if let Some(source_name) = table.keys().find(|name| !sources.contains_key(*name)) {
+                return Err(LoadSetError::LoadMetrics(LoadMetricsError::UnknownSource(
+                    source_name.clone(),
+                )));
+            }
+            for (source_name, source) in sources.iter_mut() {
+                let mut source_metrics = table.remove(source_name).unwrap_or_default();
+                let layer = source.get_default_layer_mut();
+                for (glyph_name, glyph) in layer.glyphs.iter_mut() {
+                    // Glyphs without a row, e.g. right after opting in, keep
+                    // the advance from their glif.
+                    let glif_metrics = GlyphMetrics::take(glyph);
+                    let glyph_metrics = source_metrics.remove(glyph_name).unwrap_or(glif_metrics);
+                    layer.metrics.insert(glyph_name.clone(), glyph_metrics);
+                }
+            }
+        }
+
+        let anchors_path = path.join("anchors.csv");
+        let anchors = anchors_path.exists();
+        if anchors {
+            let mut table = Self::load_anchors(&anchors_path).map_err(LoadSetError::LoadAnchors)?;
+            for (source_name, source) in sources.iter_mut() {
+                for (layer_name, layer) in source.layers.iter_mut() {
+                    for (glyph_name, glyph) in layer.glyphs.iter_mut() {
+                        // Glyphs without rows, e.g. right after opting in,
+                        // keep the anchors from their glif.
+                        let glif_anchors = take_plain_anchors(glyph);
+                        let key = (glyph_name.clone(), source_name.clone(), layer_name.clone());
+                        let glyph_anchors = table.remove(&key).unwrap_or(glif_anchors);
+                        layer.anchors.insert(glyph_name.clone(), glyph_anchors);
+                    }
+                }
+            }
+            if let Some((glyph_name, source_name, layer_name)) = table.into_keys().next() {
+                return Err(LoadSetError::LoadAnchors(LoadAnchorsError::UnknownGlyph(
+                    glyph_name,
+                    source_name,
+                    layer_name,
+                )));
+            }
+        }
+
+        Ok(Set {
+            glyph_data,
+            groups,
+            features,
+            lib,
+            metrics,
+            anchors,
+            sources,
+        })
+    }
+
+    pub fn save(&self, set_name: &Name, root_path: &Path) -> Result<(), SaveSetError> {
+        let set_path = root_path.join(format!("set.{set_name}"));
+        std::fs::create_dir(&set_path).map_err(SaveSetError::CreateDir)?;
+
+        Self::write_glyph_data(&self.glyph_data, &set_path.join("glyph_data.csv"))
+            .map_err(SaveSetError::WriteGlyphData)?;
+
+        if !self.groups.is_empty() {
+            Self::write_groups(&self.groups, &set_path.join("groups.csv"))
+                .map_err(SaveSetError::WriteGroups)?;
+        }
+
+        if !self.features.is_empty() {
+            std::fs::write(set_path.join("features.fea"), &self.features)
+                .map_err(SaveSetError::WriteFeatures)?;
+        }
+
+        if !self.lib.is_empty() {
+            plist::to_file_xml(set_path.join("lib.plist"), &self.lib)
+                .map_err(SaveSetError::WriteLib)?;
+        }
+
+        if self.metrics {
+            self.write_metrics(&set_path.join("metrics.csv"))
+                .map_err(SaveSetError::WriteMetrics)?;
+        }
+
+        if self.anchors {
+            self.write_anchors(&set_path.join("anchors.csv"))
+                .map_err(SaveSetError::WriteAnchors)?;
+        }
+
+        let glifs = self.encode_glifs()?;
+        let shared_glyphs = Self::shared_glyphs(&glifs);
+        if !shared_glyphs.is_empty() {
+            let shared_glyphs_path = set_path.join("shared_glyphs");
+            std::fs::create_dir(&shared_glyphs_path)
+                .map_err(SaveSetError::CreateSharedGlyphsDir)?;
+            for (hash, data) in &shared_glyphs {
+                std::fs::write(shared_glyphs_path.join(format!("{hash}.glif")), data)
+                    .map_err(|e| SaveSetError::WriteSharedGlyph(hash.to_string(), e))?;
+            }
+        }
+
+        for (source_name, source) in &self.sources {
+            source
+                .save(source_name, &set_path, &glifs[source_name], &shared_glyphs)
+                .map_err(|e| SaveSetError::SaveSource(source_name.clone(), e))?;
+        }
+
+        Ok(())
+    }
+
+    /// Encode the glyphs of all sources and layers of the set for saving, by
+    /// source and layer name.
+    fn encode_glifs(&self) -> Result<BTreeMap<&Name, BTreeMap<&Name, EncodedGlifs>>, SaveSetError> {
+        let mut glifs: BTreeMap<&Name, BTreeMap<&Name, EncodedGlifs>> = BTreeMap::new();
+        for (source_name, source) in &self.sources {
+            let source_glifs = glifs.entry(source_name).or_default();
+            for (layer_name, layer) in &source.layers {
+                let mut layer_glifs = EncodedGlifs::new();
+                for (glyph_name, glyph) in &layer.glyphs {
+                    let data = encode_glif(glyph)
+                        .map_err(|e| SaveSetError::EncodeGlyph(glyph_name.clone(), e))?;
+                    let hash = crate::util::content_hash(&data);
+                    layer_glifs.insert(glyph_name.clone(), (hash, data));
+                }
+                source_glifs.insert(layer_name, layer_glifs);
+            }
+        }
+        Ok(glifs)
+    }
+
+    /// The encoded glifs that occur more than once across the sources and
+    /// layers of the set, by content hash. They are stored once in the set's
+    /// shared_glyphs directory instead of in each layer.
+    ///
+    /// Different glifs with the same hash are not shared, see
+    /// [`crate::util::content_hash`].
+    fn shared_glyphs<'a>(
+        glifs: &'a BTreeMap<&Name, BTreeMap<&Name, EncodedGlifs>>,
+    ) -> BTreeMap<&'a str, &'a [u8]> {
+        let mut counts: BTreeMap<&str, (&[u8], usize)> = BTreeMap::new();
+        let mut collisions = HashSet::new();
+        for layer_glifs in glifs
+            .values()
+            .flat_map(|source_glifs| source_glifs.values())
+        {
+            for (hash, data) in layer_glifs.values() {
+                match counts.get_mut(hash.as_str()) {
+                    Some((stored, count)) if *stored == data.as_slice() => *count += 1,
+                    Some(_) => {
+                        collisions.insert(hash.as_str());
+                    }
+                    None => {
+                        counts.insert(hash, (data, 1));
+                    }
+                }
+            }
+        }
+
+        counts
+            .into_iter()
+            .filter(|(hash, (_, count))| *count > 1 && !collisions.contains(hash))
+            .map(|(hash, (data, _))| (hash, data))
+            .collect()
+    }
+
+    fn load_shared_glyphs(path: &Path) -> Result<HashMap<String, norad::Glyph>, LoadSetError> {
+        let mut shared_glyphs = HashMap::new();
+
+        if !path.exists() {
+            return Ok(shared_glyphs);
+        }
+
+        for entry in std::fs::read_dir(path)? {
+            let path = entry?.path();
+            if let Some(hash) = path
+                .file_name()
+                .and_then(|n| n.to_str())
+                .and_then(|n| n.strip_suffix(".glif"))
+            {
+                let glif = norad::Glyph::load(&path)
+                    .map_err(|e| LoadSetError::LoadSharedGlyph(path.clone(), e))?;
+                shared_glyphs.insert(hash.to_string(), glif);
+            }
+        }
+
+        Ok(shared_glyphs)
+    }
+
+    /// The lib keys owned by the set, with a value in the set's lib or in the
+    /// libs of its sources.
+    pub fn owned_lib_keys(&self) -> BTreeSet<String> {
+        let source_keys = self.sources.values().flat_map(|s| s.lib.keys());
+        self.lib.keys().chain(source_keys).cloned().collect()
+    }
+
+    pub fn glyph_coverage(&self) -> HashSet<Name> {
+        let mut glyphs = HashSet::new();
+        glyphs.extend(self.glyph_data.keys().cloned());
+        for source in self.sources.values() {
+            for layer in source.layers.values() {
+                glyphs.extend(layer.glyphs.keys().cloned());
+            }
+        }
+        glyphs
+    }
+
+    /// Take the glyphs in `glyph_names` out of the set, along with their glyph
+    /// data, group memberships, kerning, images and everything stored for
+    /// them in the sources.
+    ///
+    /// Kerning pairs with a group as the first side stay here while the group
+    /// has members left in this set, and go along otherwise, so that they are
+    /// kept in one set only.
+    pub(crate) fn split_off(&mut self, glyph_names: &HashSet<Name>) -> Set {
+        let mut split = Set {
+            glyph_data: take_entries(&mut self.glyph_data, glyph_names),
+            metrics: self.metrics,
+            anchors: self.anchors,
+            ..Default::default()
+        };
+
+        for (group_name, members) in self.groups.iter_mut() {
+            let (taken, kept): (Vec<Name>, Vec<Name>) = std::mem::take(members)
+                .into_iter()
+                .partition(|name| glyph_names.contains(name));
+            *members = kept;
+            if !taken.is_empty() {
+                split.groups.insert(group_name.clone(), taken);
+            }
+        }
+        self.groups.retain(|_, members| !members.is_empty());
+
+        for (source_name, source) in self.sources.iter_mut() {
+            let mut split_source = Source {
+                layers: BTreeMap::new(),
+                kerning: norad::Kerning::new(),
+                images: BTreeMap::new(),
+                lib: norad::Plist::new(),
+            };
+            for (layer_name, layer) in source.layers.iter_mut() {
+                let glyphs = take_entries(&mut layer.glyphs, glyph_names);
+                if glyphs.is_empty() && !layer.default {
+                    continue;
+                }
+                let (encoded, kept) = std::mem::take(&mut layer.encoded)
+                    .into_iter()
+                    .partition(|name| glyph_names.contains(name));
+                layer.encoded = kept;
+                let split_layer = Layer {
+                    glyphs,
+                    color_marks: take_entries(&mut layer.color_marks, glyph_names),
+                    default: layer.default,
+                    color: layer.color.clone(),
+                    lib: layer.lib.clone(),
+                    metrics: take_entries(&mut layer.metrics, glyph_names),
+                    anchors: take_entries(&mut layer.anchors, glyph_names),
+                    encoded,
+                };
+                split_source.layers.insert(layer_name.clone(), split_layer);
+            }
+            source
+                .layers
+                .retain(|_, layer| layer.default || !layer.glyphs.is_empty());
+
+            let goes_along = |first: &Name| {
+                glyph_names.contains(first)
+                    || (split.groups.contains_key(first) && !self.groups.contains_key(first))
+            };
+            for (first, seconds) in &source.kerning {
+                if goes_along(first) {
+                    split_source.kerning.insert(first.clone(), seconds.clone());
+                }
+            }
+            source.kerning.retain(|first, _| !goes_along(first));
+
+            let split_images = split_source.image_file_names();
+            split_source.images = source
+                .images
+                .iter()
+                .filter(|(file_name, _)| split_images.contains(*file_name))
+                .map(|(file_name, hash)| (file_name.clone(), hash.clone()))
+                .collect();
+            let kept_images = source.image_file_names();
+            source
+                .images
+                .retain(|file_name, _| kept_images.contains(file_name));
+
+            if split_source
+                .layers
+                .values()
+                .any(|layer| !layer.glyphs.is_empty())
+            {
+                split.sources.insert(source_name.clone(), split_source);
+            }
+        }
+        self.sources.retain(|_, source| {
+            !source.lib.is_empty() || source.layers.values().any(|layer| !layer.glyphs.is_empty())
+        });
+
+        split
+    }
+
+    /// Add everything of `other` to the set, the counterpart of
+    /// [`Set::split_off`].
+    ///
+    /// Metrics and anchors are moved in or out of the glyphs as this set
+    /// keeps them.
+    pub(crate) fn merge(&mut self, other: Set) {
+        self.glyph_data.extend(other.glyph_data);
+        for (group_name, members) in other.groups {
+            let our_members = self.groups.entry(group_name).or_default();
+            for member in members {
+                if !our_members.contains(&member) {
+                    our_members.push(member);
+                }
+            }
+        }
+
+        for (source_name, other_source) in other.sources {
+            let default_layer_name = other_source.default_layer_name().clone();
+            let source = self
+                .sources
+                .entry(source_name)
+                .or_insert_with(|| Source::new_with_default_layer_name(default_layer_name));
+            for (layer_name, other_layer) in other_source.layers {
+                let layer = if other_layer.default {
+                    source.get_default_layer_mut()
+                } else {
+                    source.get_or_create_layer(layer_name)
+                };
+                if layer.glyphs.is_empty() {
+                    layer.color = other_layer.color;
+                    layer.lib = other_layer.lib;
+                }
+                layer.glyphs.extend(other_layer.glyphs);
+                layer.color_marks.extend(other_layer.color_marks);
+                layer.metrics.extend(other_layer.metrics);
+                layer.anchors.extend(other_layer.anchors);
+                layer.encoded.extend(other_layer.encoded);
+                layer.set_table_options(self.metrics, self.anchors);
+            }
+            for (first, seconds) in other_source.kerning {
+                source.kerning.entry(first).or_default().extend(seconds);
+            }
+            source.images.extend(other_source.images);
+            source.lib.extend(other_source.lib);
+        }
+    }
+
+    /// The glyphs of the set with a codepoint of the Unicode script named
+    /// `script`, see [`crate::util::parse_script`]. `None` if the script is
+    /// unknown.
+    ///
+    /// This goes by the Script property, so characters used by several
+    /// scripts, like digits (Common) or most combining marks (Inherited), are
+    /// not part of any of them.
+    pub fn glyphs_of_script(&self, script: &str) -> Option<HashSet<Name>> {
+        let script = crate::util::parse_script(script)?;
+        let glyph_names = self
+            .glyph_data
+            .iter()
+            .filter(|(_, record)| record.codepoints.iter().any(|c| c.script() == script))
+            .map(|(name, _)| name.clone())
+            .collect();
+        Some(glyph_names)
+    }
+
+    fn load_glyph_data(path: &Path) -> Result<BTreeMap<Name, GlyphRecord>, LoadGlyphDataError> {
+        let mut glyph_data = BTreeMap::new();
+        let mut reader = csv::Reader::from_path(path).map_err(LoadGlyphDataError::Csv)?;
+
+        type Record = (String, Option<String>, Option<String>, Option<String>, bool);
+        for result in reader.deserialize() {
+            let record: Record = result.map_err(LoadGlyphDataError::Csv)?;
+
+            let glyph_name = Name::new(&record.0)
+                .map_err(|e| LoadGlyphDataError::InvalidGlyphName(record.0, e))?;
+            let codepoints = match &record.2 {
+                Some(codepoints_string) => {
+                    Self::parse_codepoints(codepoints_string).map_err(|e| {
+                        LoadGlyphDataError::InvalidCodepoint(
+                            glyph_name.clone(),
+                            codepoints_string.clone(),
+                            e,
+                        )
+                    })?
+                }
+                None => norad::Codepoints::new([]),
+            };
+
+            let opentype_category = match record.3 {
+                Some(s) => s.parse().map_err(|e: &'static str| {
+                    LoadGlyphDataError::InvalidOpenTypeCategory(glyph_name.clone(), s, anyhow!(e))
+                })?,
+                None => OpenTypeCategory::default(),
+            };
+
+            glyph_data.insert(
+                glyph_name,
+                GlyphRecord {
+                    postscript_name: record.1,
+                    codepoints,
+                    opentype_category,
+                    export: record.4,
+                },
+            );
+        }
+
+        Ok(glyph_data)
+    }
+
+    // NOTE: Use anyhow::Error here because we use anyhow's Context trait in main.
+    // Something about Sync and Send.
+    fn parse_codepoints(v: &str) -> Result<Codepoints, anyhow::Error> {
+        let mut codepoints = Vec::new();
+        let mut seen = HashSet::new();
+
+        for codepoint in v.split_whitespace() {
+            let codepoint = u32::from_str_radix(codepoint, 16)?;
+            let codepoint = char::try_from(codepoint)?;
+            if seen.insert(codepoint) {
+                codepoints.push(codepoint);
+            }
+        }
+
+        Ok(norad::Codepoints::new(codepoints))
+    }
+
+    fn write_glyph_data(
+        glyph_data: &BTreeMap<Name, GlyphRecord>,
+        path: &Path,
+    ) -> Result<(), csv::Error> {
+        let mut writer = csv::Writer::from_path(path)?;
+
+        writer.write_record([
+            "name",
+            "postscript_name",
+            "codepoints",
+            "opentype_category",
+            "export",
+        ])?;
+
+        for glyph_name in glyph_data.keys() {
+            let record = &glyph_data[glyph_name];
+            let codepoints_str: String = record
+                .codepoints
+                .iter()
+                .map(|c| format!("{:04X}", c as usize))
+                .collect::<Vec<_>>()
+                .join(" ");
+            writer.serialize((
+                glyph_name,
+                &record.postscript_name,
+                codepoints_str,
+                &record.opentype_category,
+                record.export,
+            ))?;
+        }
+        writer.flush()?;
+
+        Ok(())
+    }
+
+    fn load_groups(path: &Path) -> Result<norad::Groups, csv::Error> {
+        let mut groups = norad::Groups::new();
+
+        if !path.exists() {
+            return Ok(groups);
+        }
+
+        let mut reader = csv::Reader::from_path(path)?;
+        for result in reader.deserialize() {
+            let (group_name, glyph_name): (Name, Name) = result?;
+            groups.entry(group_name).or_default().push(glyph_name);
+        }
+
+        Ok(groups)
+    }
+
+    /// Load the metrics.csv table, with a width and a height column per
+    /// source. Empty cells are left out.
+    fn load_metrics(
+        path: &Path,
+    ) -> Result<BTreeMap<Name, BTreeMap<Name, GlyphMetrics>>, LoadMetricsError> {
+        let mut reader = csv::Reader::from_path(path).map_err(LoadMetricsError::Csv)?;
+
+        let headers = reader.headers().map_err(LoadMetricsError::Csv)?.clone();
+        let mut source_names = Vec::new();
+        let columns: Vec<&str> = headers.iter().skip(1).collect();
+        for pair in columns.chunks(2) {
+            let source_name = match pair {
+                [width, height] => width
+                    .strip_prefix("width.")
+                    .filter(|name| height.strip_prefix("height.") == Some(*name)),
+                _ => None,
+            }
+            .ok_or_else(|| LoadMetricsError::InvalidColumns(pair.join(",")))?;
+            let source_name = Name::new(source_name)
+                .map_err(|e| LoadMetricsError::InvalidSourceName(source_name.into(), e))?;
+            source_names.push(source_name);
+        }
+
+        let mut metrics: BTreeMap<Name, BTreeMap<Name, GlyphMetrics>> = BTreeMap::new();
+        for result in reader.records() {
+            let record = result.map_err(LoadMetricsError::Csv)?;
+            let glyph_name = record.get(0).unwrap_or_default();
+            let glyph_name = Name::new(glyph_name)
+                .map_err(|e| LoadMetricsError::InvalidGlyphName(glyph_name.into(), e))?;
+
+            for (i, source_name) in source_names.iter().enumerate() {
+                let width = record.get(1 + 2 * i).unwrap_or_default();
+                let height = record.get(2 + 2 * i).unwrap_or_default();
+                if width.is_empty() {
+                    continue;
+                }
+                let parse = |v: &str| {
+                    if v.is_empty() {
+                        return Ok(0.0);
+                    }
+                    v.parse::<f64>().map_err(|e| {
+                        LoadMetricsError::InvalidValue(glyph_name.clone(), v.into(), e)
+                    })
+                };
+                let glyph_metrics = GlyphMetrics {
+                    width: parse(width)?,
+                    height: parse(height)?,
+                };
+                metrics
+                    .entry(source_name.clone())
+                    .or_default()
+                    .insert(glyph_name.clone(), glyph_metrics);
+            }
+        }
+
+        Ok(metrics)
+    }
+
+    fn write_metrics(&self, path: &Path) -> Result<(), csv::Error> {
+        let mut writer = csv::Writer::from_path(path)?;
+
+        let mut headers = vec!["glyph".to_string()];
+        for source_name in self.sources.keys() {
+            headers.push(format!("width.{source_name}"));
+            headers.push(format!("height.{source_name}"));
+        }
+        writer.write_record(&headers)?;
+
+        let glyph_names: BTreeSet<&Name> = self
+            .sources
+            .values()
+            .flat_map(|source| source.get_default_layer().metrics.keys())
+            .collect();
+        for glyph_name in glyph_names {
+            let mut record = vec![glyph_name.to_string()];
+            for source in self.sources.values() {
+                match source.get_default_layer().metrics.get(glyph_name) {
+                    Some(metrics) => {
+                        record.push(metrics.width.to_string());
+                        record.push(metrics.height.to_string());
+                    }
+                    None => record.extend([String::new(), String::new()]),
+                }
+            }
+            writer.write_record(&record)?;
+        }
+        writer.flush()?;
+
+        Ok(())
+    }
+
+    /// Load the anchors.csv table, keyed by glyph, source and layer name.
+    fn load_anchors(
+        path: &Path,
+    ) -> Result<BTreeMap<AnchorsKey, Vec<norad::Anchor>>, LoadAnchorsError> {
+        let mut anchors: BTreeMap<AnchorsKey, Vec<norad::Anchor>> = BTreeMap::new();
+
+        let mut reader = csv::Reader::from_path(path).map_err(LoadAnchorsError::Csv)?;
+        for result in reader.deserialize() {
+            type Record = (Name, Name, Name, Name, f64, f64);
+            let (glyph_name, source_name, layer_name, name, x, y): Record =
+                result.map_err(LoadAnchorsError::Csv)?;
+            anchors
+                .entry((glyph_name, source_name, layer_name))
+                .or_default()
+                .push(norad::Anchor::new(x, y, Some(name), None, None, None));
+        }
+
+        Ok(anchors)
+    }
+
+    fn write_anchors(&self, path: &Path) -> Result<(), csv::Error> {
+        let mut rows = Vec::new();
+        for (source_name, source) in &self.sources {
+            for (layer_name, layer) in &source.layers {
+                for (glyph_name, anchors) in &layer.anchors {
+                    for anchor in anchors {
+                        rows.push((glyph_name, source_name, layer_name, anchor));
+                    }
+                }
+            }
+        }
+        // Group the rows by glyph for review, keeping the anchor order.
+        rows.sort_by_key(|(glyph_name, source_name, layer_name, _)| {
+            (*glyph_name, *source_name, *layer_name)
+        });
+
+        let mut writer = csv::Writer::from_path(path)?;
+
+        writer.write_record(["glyph", "source", "layer", "name", "x", "y"])?;
+        for (glyph_name, source_name, layer_name, anchor) in rows {
+            let name = anchor.name.as_ref().map_or("", |name| name.as_str());
+            writer.write_record([
+                glyph_name.as_str(),
+                source_name.as_str(),
+                layer_name.as_str(),
+                name,
+                anchor.x.to_string().as_str(),
+                anchor.y.to_string().as_str(),
+            ])?;
+        }
+        writer.flush()?;
+
+        Ok(())
+    }
+
+    fn write_groups(groups: &norad::Groups, path: &Path) -> Result<(), csv::Error> {
+        let mut writer = csv::Writer::from_path(path)?;
+
+        writer.write_record(["group", "glyph"])?;
+        for (group_name, members) in groups {
+            for glyph_name in members {
+                writer.serialize((group_name, glyph_name))?;
+            }
+        }
+        writer.flush()?;
+
+        Ok(())
+    }
+}
+
+impl Default for Source {
+    fn default() -> Self {
+        let layer = Layer {
+            default: true,
+            ..Default::default()
+        };
+        Self {
+            layers: BTreeMap::from([(Name::new("public.default").unwrap(), layer)]),
+            kerning: Default::default(),
+            images: Default::default(),
+            lib: norad::Plist::new(),
+        }
+    }
+}
+
+impl Source {
+    pub(crate) fn from_path(
+        path: &Path,
+        shared_glyphs: &HashMap<String, norad::Glyph>,
+    ) -> Result<Self, LoadSourceError> {
+        let mut layers = BTreeMap::new();
+        let mut found_default = false;
+
+        for entry in std::fs::read_dir(path)? {
+            let entry = entry?;
+            let path = entry.path();
+            if let Some(file_name) = path.file_name() {
+                let metadata = entry.metadata()?;
+                if metadata.is_dir()
+                    && (file_name == "glyphs" || file_name.to_string_lossy().starts_with("glyphs."))
+                {
+                    let (layer, layerinfo) = Layer::from_path(&path, shared_glyphs)
+                        .map_err(|e| LoadSourceError::LoadLayer(path.clone(), e))?;
+
+                    // All non-default layer names start with a dot after "glyphs".
+                    // Hope that we don't bump into filesystem case-sensitivity
+                    // issues.
+                    if file_name == "glyphs" {
+                        found_default = true;
+                    }
+                    layers.insert(layerinfo.name, layer);
+                }
+            }
+        }
+
+        if !found_default {
+            return Err(LoadSourceError::NoDefaultLayer);
+        }
+
+        let kerning =
+            Self::load_kerning(&path.join("kerning.csv")).map_err(LoadSourceError::LoadKerning)?;
+
+        let images =
+            Self::load_images(&path.join("images.csv")).map_err(LoadSourceError::LoadImages)?;
+
+        let lib_path = path.join("lib.plist");
+        let lib = if lib_path.exists() {
+            plist::from_file(lib_path).map_err(LoadSourceError::LoadLib)?
+        } else {
+            norad::Plist::new()
+        };
+
+        Ok(Source {
+            layers,
+            kerning,
+            images,
+            lib,
+        })
+    }
+
+    pub fn default_layer_name(&self) -> &Name {
+        self.layers
+            .iter()
+            .find(|(_, layer)| layer.default)
+            .map(|(name, _)| name)
+            .unwrap()
+    }
+
+    pub fn get_default_layer(&self) -> &Layer {
+        self.layers.values().find(|layer| layer.default).unwrap()
+    }
+
+    pub fn get_default_layer_mut(&mut self) -> &mut Layer {
+        self.layers
+            .values_mut()
+            .find(|layer| layer.default)
+            .unwrap()
+    }
+
+    pub fn get_or_create_layer(&mut self, name: Name) -> &mut Layer {
+        self.layers.entry(name).or_default()
+    }
+
+    pub fn new_with_default_layer_name(name: Name) -> Self {
+        let layer = Layer {
+            default: true,
+            ..Default::default()
+        };
+        Self {
+            layers: BTreeMap::from([(name, layer)]),
+            kerning: Default::default(),
+            images: Default::default(),
+            lib: norad::Plist::new(),
+        }
+    }
+
+    pub(crate) fn save(
+        &self,
+        source_name: &str,
+        set_path: &Path,
+        glifs: &BTreeMap<&Name, EncodedGlifs>,
+        shared_glyphs: &BTreeMap<&str, &[u8]>,
+    ) -> Result<(), SaveSourceError> {
+        let source_path = set_path.join(format!("source.{source_name}"));
+        std::fs::create_dir(&source_path).map_err(SaveSourceError::CreateDir)?;
+
+        let mut existing_layer_names = HashSet::new();
+        for (layer_name, layer) in &self.layers {
+            layer
+                .save(
+                    layer_name,
+                    &source_path,
+                    &mut existing_layer_names,
+                    &glifs[layer_name],
+                    shared_glyphs,
+                )
+                .map_err(|e| SaveSourceError::SaveLayer(layer_name.clone(), e))?;
+        }
+
+        if !self.kerning.is_empty() {
+            Self::write_kerning(&source_path.join("kerning.csv"), &self.kerning)
+                .map_err(SaveSourceError::WriteKerning)?;
+        }
+
+        if !self.images.is_empty() {
+            Self::write_images(&self.images, &source_path.join("images.csv"))
+                .map_err(SaveSourceError::WriteImages)?;
+        }
+
+        if !self.lib.is_empty() {
+            plist::to_file_xml(source_path.join("lib.plist"), &self.lib)
+                .map_err(SaveSourceError::WriteLib)?;
+        }
+
+        Ok(())
+    }
+
+    /// The UFO image file names referenced by the glyphs of the source.
+    fn image_file_names(&self) -> HashSet<String> {
+        self.layers
+            .values()
+            .flat_map(|layer| layer.glyphs.values())
+            .filter_map(|glyph| glyph.image.as_ref())
+            .map(|image| image.file_name.to_string_lossy().into_owned())
+            .collect()
+    }
+
+    fn load_kerning(path: &Path) -> Result<norad::Kerning, csv::Error> {
+        let mut kerning = norad::Kerning::new();
+
+        if !path.exists() {
+            return Ok(kerning);
+        }
+
+        let mut reader = csv::Reader::from_path(path)?;
+        for result in reader.deserialize() {
+            let (first, second, value): (Name, Name, f64) = result?;
+            kerning.entry(first).or_default().insert(second, value);
+        }
+
+        Ok(kerning)
+    }
+
+    fn write_kerning(path: &Path, kerning: &norad::Kerning) -> Result<(), csv::Error> {
+        let mut writer = csv::Writer::from_path(path)?;
+
+        writer.write_record(["first", "second", "value"])?;
+        for (first, seconds) in kerning {
+            for (second, value) in seconds {
+                // NOTE: Format through Display so that whole numbers are
+                // written without a trailing ".0".
+                writer.write_record([
+                    first.as_str(),
+                    second.as_str(),
+                    value.to_string().as_str(),
+                ])?;
+            }
+        }
+        writer.flush()?;
+
+        Ok(())
+    }
+
+    fn load_images(path: &Path) -> Result<BTreeMap<String, String>, csv::Error> {
+        let mut images = BTreeMap::new();
+
+        if !path.exists() {
+            return Ok(images);
+        }
+
+        let mut reader = csv::Reader::from_path(path)?;
+        for result in reader.deserialize() {
+            let (file_name, hash): (String, String) = result?;
+            images.insert(file_name, hash);
+        }
+
+        Ok(images)
+    }
+
+    fn write_images(images: &BTreeMap<String, String>, path: &Path) -> Result<(), csv::Error> {
+        let mut writer = csv::Writer::from_path(path)?;
+
+        writer.write_record(["file_name", "hash"])?;
+        for (file_name, hash) in images {
+            writer.serialize((file_name, hash))?;
+        }
+        writer.flush()?;
+
+        Ok(())
+    }
+}
+
+impl Layer {
+    pub(crate) fn from_path(
+        path: &Path,
+        shared_glyphs: &HashMap<String, norad::Glyph>,
+    ) -> Result<(Self, LayerInfo), LoadLayerError> {
+        let mut glyphs = BTreeMap::new();
+        let color_marks = Self::load_color_marks(&path.join("color_marks.csv"))
+            .map_err(LoadLayerError::LoadColorMarks)?;
+        let encoded = Self::load_encoded(&path.join("encoded_glyphs.csv"))
+            .map_err(LoadLayerError::LoadEncodedGlyphs)?;
+        let layerinfo: LayerInfo = plist::from_file(path.join("layerinfo.plist"))
+            .map_err(LoadLayerError::LoadLayerInfo)?;
+
+        for entry in std::fs::read_dir(path)? {
+            let path = entry?.path();
+            if path.is_file() && path.extension().is_some_and(|n| n == "glif") {
+                let glif = norad::Glyph::load(&path)
+                    .map_err(|e| LoadLayerError::LoadGlyph(path.clone(), e))?;
+                glyphs.insert(glif.name().clone(), glif);
+            }
+        }
+
+        let shared_glyphs_path = path.join("shared_glyphs.csv");
+        if shared_glyphs_path.exists() {
+            let mut reader = csv::Reader::from_path(&shared_glyphs_path)
+                .map_err(LoadLayerError::LoadSharedGlyphs)?;
+            for result in reader.deserialize() {
+                let (glyph_name, hash): (Name, String) =
+                    result.map_err(LoadLayerError::LoadSharedGlyphs)?;
+                let glif = shared_glyphs.get(&hash).ok_or_else(|| {
+                    LoadLayerError::UnknownSharedGlyph(glyph_name.clone(), hash.clone())
+                })?;
+                if *glif.name() != glyph_name {
+                    return Err(LoadLayerError::SharedGlyphNameMismatch(
+                        glyph_name,
+                        hash,
+                        glif.name().clone(),
+                    ));
+                }
+                glyphs.insert(glyph_name, glif.clone());
+            }
+        }
+
+        Ok((
+            Layer {
+                glyphs,
+                color_marks,
+                default: path.file_name() == Some(OsStr::new("glyphs")),
+                color: layerinfo.color.clone(),
+                lib: layerinfo.lib.clone(),
+                metrics: BTreeMap::new(),
+                anchors: BTreeMap::new(),
+                encoded,
+            },
+            layerinfo,
+        ))
+    }
+
+    /// Take the glyphs in `glyph_names` from a UFO layer.
+    ///
+    /// The codepoints of the glyphs are removed, as they are stored in the
+    /// glyph data of a set. Glyphs of other layers than the default one that
+    /// had codepoints are recorded in [`Layer::encoded`]. Depending on
+    /// `options`, advances and anchors are moved out of the glyphs, too.
+    pub(crate) fn from_ufo_layer(
+        layer: &norad::Layer,
+        glyph_names: &HashSet<Name>,
+        options: LayerImportOptions,
+    ) -> Self {
+        let mut glyphs = BTreeMap::new();
+        let mut color_marks = BTreeMap::new();
+        let mut metrics = BTreeMap::new();
+        let mut anchors = BTreeMap::new();
+        let mut encoded = BTreeSet::new();
+
+        for glyph in layer
+            .iter()
+            .filter(|g| glyph_names.contains(g.name().as_str()))
+        {
+            let mut our_glyph = glyph.clone();
+            if let Some(color_string) = our_glyph.lib.remove("public.markColor") {
+                // FIXME: We roundtrip color here so that we round up front to
+                // make roundtrip equality testing easier.
+                let our_color = Color::from_str(color_string.as_string().unwrap()).unwrap();
+                let our_color = Color::from_str(&our_color.to_rgba_string()).unwrap();
+                color_marks.insert(glyph.name().clone(), our_color);
+            }
+            if !options.default && !our_glyph.codepoints.is_empty() {
+                encoded.insert(glyph.name().clone());
+            }
+            our_glyph.codepoints.clear();
+            if options.metrics {
+                metrics.insert(glyph.name().clone(), GlyphMetrics::take(&mut our_glyph));
+            }
+            if options.anchors {
+                anchors.insert(glyph.name().clone(), take_plain_anchors(&mut our_glyph));
+            }
+            glyphs.insert(glyph.name().clone(), our_glyph);
+        }
+
+        Self {
+            glyphs,
+            color_marks,
+            default: options.default,
+            color: layer.color.clone(),
+            lib: layer.lib.clone(),
+            metrics,
+            anchors,
+            encoded,
+        }
+    }
+
+    /// The glyphs of the layer that are not recorded as having codepoints.
+    fn glyphs_without_codepoints(&self) -> Vec<Name> {
+        self.glyphs
+            .keys()
+            .filter(|name| !self.encoded.contains(*name))
+            .cloned()
+            .collect()
+    }
+
+    /// Move advances and anchors out of or back into the glyphs, depending on
+    /// whether the set keeps them in its metrics and anchors tables.
+    fn set_table_options(&mut self, metrics: bool, anchors: bool) {
+        for (name, glyph) in self.glyphs.iter_mut() {
+            if metrics && self.default {
+                self.metrics
+                    .entry(name.clone())
+                    .or_insert_with(|| GlyphMetrics::take(glyph));
+            } else if let Some(glyph_metrics) = self.metrics.remove(name) {
+                glyph_metrics.apply(glyph);
+            }
+            if anchors {
+                self.anchors
+                    .entry(name.clone())
+                    .or_insert_with(|| take_plain_anchors(glyph));
+            } else if let Some(glyph_anchors) = self.anchors.remove(name) {
+                glyph.anchors.extend(glyph_anchors);
+            }
+        }
+    }
+
+    /// Whether the glyph `name` is the same in both layers, including the
+    /// metrics and anchors kept outside of it.
+    fn same_glyph(&self, other: &Layer, name: &Name) -> bool {
+        self.glyphs.get(name) == other.glyphs.get(name)
+            && self.metrics.get(name) == other.metrics.get(name)
+            && self.anchors.get(name) == other.anchors.get(name)
+            && self.encoded.contains(name) == other.encoded.contains(name)
+    }
+
+    /// Write the glyphs and layer info into a UFO layer, re-applying the
+    /// codepoints from `glyph_data` in the default layer and to the glyphs
+    /// of other layers that had them.
+    pub(crate) fn into_ufo_layer(
+        self,
+        ufo_layer: &mut norad::Layer,
+        glyph_data: &BTreeMap<Name, GlyphRecord>,
+    ) {
+        ufo_layer.color = self.color;
+        ufo_layer.lib = self.lib;
+        for (name, mut glyph) in self.glyphs {
+            if let Some(c) = self.color_marks.get(&name) {
+                glyph
+                    .lib
+                    .insert("public.markColor".into(), c.to_rgba_string().into());
+            }
+            if let Some(metrics) = self.metrics.get(&name) {
+                metrics.apply(&mut glyph);
+            }
+            if let Some(anchors) = self.anchors.get(&name) {
+                glyph.anchors.extend(anchors.iter().cloned());
+            }
+            let encoded = self.default || self.encoded.contains(&name);
+            if let Some(record) = glyph_data.get(&name).filter(|_| encoded) {
+                glyph.codepoints = record.codepoints.clone();
+            }
+            ufo_layer.insert_glyph(glyph);
+        }
+    }
+
+    // NOTE: Keep in sync with into_ufo_layer.
+    // TODO: Make a proper to-norad export API and dogfood it.
+    #[cfg(test)]
+    pub(crate) fn export_glyph(
+        &self,
+        name: &str,
+        glyph_data: &BTreeMap<Name, GlyphRecord>,
+    ) -> norad::Glyph {
+        let mut glyph = self.glyphs[name].clone();
+        if let Some(c) = self.color_marks.get(name) {
+            glyph
+                .lib
+                .insert("public.markColor".into(), c.to_rgba_string().into());
+        }
+        if let Some(metrics) = self.metrics.get(name) {
+            metrics.apply(&mut glyph);
+        }
+        if let Some(anchors) = self.anchors.get(name) {
+            glyph.anchors.extend(anchors.iter().cloned());
+        }
+        let encoded = self.default || self.encoded.contains(name);
+        if let Some(record) = glyph_data.get(name).filter(|_| encoded) {
+            glyph.codepoints = record.codepoints.clone();
+        }
+        glyph
+    }
+
+    fn load_encoded(path: &Path) -> Result<BTreeSet<Name>, csv::Error> {
+        let mut encoded = BTreeSet::new();
+
+        if !path.exists() {
+            return Ok(encoded);
+        }
+
+        let mut reader = csv::Reader::from_path(path)?;
+        for result in reader.deserialize() {
+            let (name,): (Name,) = result?;
+            encoded.insert(name);
+        }
+
+        Ok(encoded)
+    }
+
+    fn load_color_marks(path: &Path) -> Result<BTreeMap<Name, Color>, csv::Error> {
+        let mut color_marks = BTreeMap::new();
+
+        if !path.exists() {
+            return Ok(color_marks);
+        }
+
+        let mut reader = csv::Reader::from_path(path)?;
+        for result in reader.deserialize() {
+            let record: (Name, Color) = result?;
+            color_marks.insert(record.0, record.1);
+        }
+
+        Ok(color_marks)
+    }
+
+    pub(crate) fn save(
+        &self,
+        layer_name: &Name,
+        source_path: &Path,
+        existing_layer_names: &mut HashSet<String>,
+        glifs: &EncodedGlifs,
+        shared_glyphs: &BTreeMap<&str, &[u8]>,
+    ) -> Result<(), SaveLayerError> {
+        // A source must have a default layer to load again.
+        if self.glyphs.is_empty() && !self.default {
+            return Ok(());
+        }
+
+        let layer_path = if self.default {
+            source_path.join("glyphs")
+        } else {
+            let path = source_path.join(crate::util::default_file_name_for_layer_name(
+                layer_name,
+                existing_layer_names,
+            ));
+            existing_layer_names.insert(path.to_string_lossy().to_string());
+            path
+        };
+        std::fs::create_dir(&layer_path).map_err(SaveLayerError::CreateDir)?;
+
+        plist::to_file_xml(
+            layer_path.join("layerinfo.plist"),
+            &LayerInfo {
+                name: layer_name.clone(),
+                color: self.color.clone(),
+                lib: self.lib.clone(),
+            },
+        )
+        .map_err(SaveLayerError::WriteLayerInfo)?;
+
+        let mut existing_glyph_names = HashSet::new();
+        let mut shared_rows = Vec::new();
+        for (glyph_name, (hash, data)) in glifs {
+            // Only refer to the shared glif if it really is the same, the
+            // hash alone may collide.
+            if shared_glyphs.get(hash.as_str()) == Some(&data.as_slice()) {
+                shared_rows.push((glyph_name, hash.clone()));
+                continue;
+            }
+            let filename =
+                crate::util::default_file_name_for_glyph_name(glyph_name, &existing_glyph_names);
+            let glyph_path = layer_path.join(&filename);
+            std::fs::write(&glyph_path, data)
+                .map_err(|e| SaveLayerError::WriteGlyph(glyph_name.clone(), e))?;
+            existing_glyph_names.insert(filename.to_string_lossy().to_string());
+        }
+
+        if !shared_rows.is_empty() {
+            Self::write_shared_glyphs(&layer_path.join("shared_glyphs.csv"), &shared_rows)
+                .map_err(SaveLayerError::WriteSharedGlyphs)?;
+        }
+
+        Self::write_color_marks(&layer_path.join("color_marks.csv"), &self.color_marks)
+            .map_err(SaveLayerError::WriteColorMarks)?;
+
+        if !self.encoded.is_empty() {
+            Self::write_encoded(&layer_path.join("encoded_glyphs.csv"), &self.encoded)
+                .map_err(SaveLayerError::WriteEncodedGlyphs)?;
+        }
+
+        Ok(())
+    }
+
+    fn write_shared_glyphs(path: &Path, rows: &[(&Name, String)]) -> Result<(), csv::Error> {
+        let mut writer = csv::Writer::from_path(path)?;
+
+        writer.write_record(["name", "hash"])?;
+        for row in rows {
+            writer.serialize(row)?;
+        }
+        writer.flush()?;
+
+        Ok(())
+    }
+
+    fn write_color_marks(
+        path: &Path,
+        color_marks: &BTreeMap<Name, Color>,
+    ) -> Result<(), csv::Error> {
+        let mut writer = csv::Writer::from_path(path)?;
+
+        writer.write_record(["name", "color"])?;
+        for (name, color) in color_marks {
+            writer.serialize((name, color))?;
+        }
+        writer.flush()?;
+
+        Ok(())
+    }
+
+    fn write_encoded(path: &Path, encoded: &BTreeSet<Name>) -> Result<(), csv::Error> {
+        let mut writer = csv::Writer::from_path(path)?;
+
+        writer.write_record(["name"])?;
+        for name in encoded {
+            writer.serialize((name,))?;
+        }
+        writer.flush()?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    macro_rules! collect_names {
+        ($($x: expr), + $(,) ?) => {
+            [$($x),+].iter().map(|n| Name::new(n).unwrap()).collect()
+        };
+    }
+
+    macro_rules! name {
+        ($e: expr) => {
+            Name::new($e).unwrap()
+        };
+    }
+
+    #[test]
+    fn load_empty() {
+        let tempdir = tempfile::TempDir::new().unwrap();
+
+        let fontgarden = Fontgarden::new();
+        fontgarden.save(tempdir.path()).unwrap();
+        let fontgarden2 = Fontgarden::from_path_with_report(tempdir.path()).unwrap().0;
+
+        assert_eq!(fontgarden, fontgarden2);
+    }
+
+    #[test]
+    fn save_load() {
+        let mut fontgarden = Fontgarden::new();
+
+        let fonts = [
+            norad::Font::load("testdata/MutatorSansLightWide.ufo").unwrap(),
+            norad::Font::load("testdata/MutatorSansLightCondensed.ufo").unwrap(),
+        ];
+
+        let sets = vec![
+            (name!("Latin"), collect_names!["A", "Aacute", "S"]),
+            (
+                name!("Arrows"),
+                collect_names!["quotedblbase", "quotedblleft", "comma"],
+            ),
+            (name!("Punctuation"), collect_names!["arrowleft"]),
+            (name!("default"), collect_names!["acute"]),
+        ];
+
+        for font in &fonts {
+            let source_name = crate::util::guess_source_name(font).unwrap();
+            for (set_name, set_glyphs) in &sets {
+                fontgarden
+                    .import_with_options(
+                        font,
+                        set_glyphs,
+                        set_name,
+                        &source_name,
+                        &ImportOptions::default(),
+                    )
+                    .unwrap();
+            }
+        }
+
+        for set in fontgarden.sets.values() {
+            for source in set.sources.values() {
+                for (layer_name, layer) in &source.layers {
+                    if layer_name.as_ref() == "foreground" {
+                        assert!(layer.default)
+                    } else {
+                        assert!(!layer.default)
+                    }
+                }
+            }
+        }
+
+        fontgarden.features = "languagesystem DFLT dflt;\n".into();
+        fontgarden.sets.get_mut("Latin").unwrap().features =
+            "feature kern { pos A V -50; } kern;\n".into();
+
+        let tempdir = tempfile::tempdir().unwrap();
+        fontgarden.save(tempdir.path()).unwrap();
+        let fontgarden2 = Fontgarden::from_path_with_report(tempdir.path()).unwrap().0;
+
+        // TODO: write per-field assert_eq for easier diffs.
+        assert_eq!(fontgarden, fontgarden2);
+    }
+
+    #[test]
+    fn export_import() {
+        let mut fontgarden = Fontgarden::new();
+
+        let mut fonts = [
+            norad::Font::load("testdata/MutatorSansLightWide.ufo").unwrap(),
+            norad::Font::load("testdata/MutatorSansLightCondensed.ufo").unwrap(),
+        ];
+        for font in &mut fonts {
+            scrub_colors(font);
+        }
+        let all_glyphs: HashSet<Name> = fonts[0].iter_names().collect();
+
+        let latin_glyphs: HashSet<Name> = collect_names!["A", "Aacute", "S"];
+        let default_glyphs: HashSet<Name> = all_glyphs.difference(&latin_glyphs).cloned().collect();
+        let sets = vec![
+            (name!("Latin"), latin_glyphs),
+            (name!("default"), default_glyphs),
+        ];
+
+        let mut source_names = HashSet::new();
+        for font in &fonts {
+            let source_name = crate::util::guess_source_name(font).unwrap();
+            source_names.insert(source_name.clone());
+
+            for (set_name, set_glyphs) in &sets {
+                fontgarden
+                    .import_with_options(
+                        font,
+                        set_glyphs,
+                        set_name,
+                        &source_name,
+                        &ImportOptions::default(),
+                    )
+                    .unwrap();
+            }
+        }
+
+        let roundtripped_ufos = fontgarden.export(&all_glyphs, &source_names).unwrap();
+
+        assert_font_eq(&fonts[0], &roundtripped_ufos["LightWide"]);
+        assert_font_eq(&fonts[1], &roundtripped_ufos["LightCondensed"]);
+    }
+
+    #[test]
+    fn update_sets() {
+        let mut fontgarden = Fontgarden::new();
+
+        let (mut ufo1, mut ufo2) = (
+            norad::Font::load("testdata/MutatorSansLightWide.ufo").unwrap(),
+            norad::Font::load("testdata/MutatorSansLightCondensed.ufo").unwrap(),
+        );
+
+        let sets = vec![
+            (name!("Latin"), collect_names!["A"]),
+            (name!("default"), collect_names!["arrowleft"]),
+        ];
+
+        for font in [&ufo1, &ufo2] {
+            let source_name = crate::util::guess_source_name(font).unwrap();
+
+            for (set_name, set_glyphs) in &sets {
+                fontgarden
+                    .import_with_options(
+                        font,
+                        set_glyphs,
+                        set_name,
+                        &source_name,
+                        &ImportOptions::default(),
+                    )
+                    .unwrap();
+            }
+        }
+
+        // Assert the imported glyphs are the same as the UFO ones.
+        assert_glyph_eq(
+            ufo1.get_glyph("A").unwrap(),
+            &fontgarden.sets["Latin"].sources["LightWide"].layers["foreground"]
+                .export_glyph("A", &fontgarden.sets["Latin"].glyph_data),
+        );
+        assert_glyph_eq(
+            ufo2.get_glyph("arrowleft").unwrap(),
+            &fontgarden.sets["default"].sources["LightCondensed"].layers["foreground"]
+                .export_glyph("arrowleft", &fontgarden.sets["default"].glyph_data),
+        );
+
+        // Modify the glyphs in the UFOs.
+        ufo1.get_glyph_mut("A")
+            .unwrap()
+            .lib
+            .insert("public.markColor".into(), "1,1,1,1".into());
+        ufo2.get_glyph_mut("arrowleft")
+            .unwrap()
+            .lib
+            .insert("bbbb".into(), 1.into());
+
+        // Reimporting the glyphs into any one set should update them in the set
+        // they are in already.
+        let both_glyphs = collect_names!["A", "arrowleft"];
+        for font in [&ufo1, &ufo2] {
+            let source_name = crate::util::guess_source_name(font).unwrap();
+            fontgarden
+                .import_with_options(
+                    font,
+                    &both_glyphs,
+                    &sets[0].0,
+                    &source_name,
+                    &ImportOptions::default(),
+                )
+                .unwrap();
+        }
+
+        assert_glyph_eq(
+            ufo1.get_glyph("A").unwrap(),
+            &fontgarden.sets["Latin"].sources["LightWide"].layers["foreground"]
+                .export_glyph("A", &fontgarden.sets["Latin"].glyph_data),
+        );
+        assert_glyph_eq(
+            ufo2.get_glyph("arrowleft").unwrap(),
+            &fontgarden.sets["default"].sources["LightCondensed"].layers["foreground"]
+                .export_glyph("arrowleft", &fontgarden.sets["default"].glyph_data),
+        );
+    }
+
+    #[test]
+    fn follow_components() {
+        use pretty_assertions::assert_eq;
+
+        let mut fontgarden = Fontgarden::new();
+
+        let fonts = [
+            norad::Font::load("testdata/MutatorSansLightWide.ufo").unwrap(),
+            norad::Font::load("testdata/MutatorSansLightCondensed.ufo").unwrap(),
+        ];
+
+        let sets = vec![
+            (name!("Latin"), collect_names!["Aacute"]),
+            (name!("Punctuation"), collect_names!["semicolon"]),
+        ];
+        let all_glyphs: HashSet<Name> = collect_names!["Aacute", "semicolon"];
+
+        for font in &fonts {
+            let source_name = crate::util::guess_source_name(font).unwrap();
+            for (set_name, set_glyphs) in &sets {
+                fontgarden
+                    .import_with_options(
+                        font,
+                        set_glyphs,
+                        set_name,
+                        &source_name,
+                        &ImportOptions::default(),
+                    )
+                    .unwrap();
+            }
+        }
+
+        assert_eq!(
+            vec![
+                (
+                    "Latin",
+                    "LightCondensed",
+                    "foreground",
+                    vec!["A", "Aacute", "acute"],
+                ),
+                ("Latin", "LightCondensed", "support", vec!["A"]),
+                (
+                    "Latin",
+                    "LightWide",
+                    "foreground",
+                    vec!["A", "Aacute", "acute"],
+                ),
+                (
+                    "Punctuation",
+                    "LightCondensed",
+                    "foreground",
+                    vec!["comma", "period", "semicolon"],
+                ),
+                (
+                    "Punctuation",
+                    "LightWide",
+                    "foreground",
+                    vec!["comma", "period", "semicolon"],
+                ),
+            ],
+            glyphs_of_fontgarden(&fontgarden),
+        );
+
+        let source_names = collect_names!["LightWide", "LightCondensed"];
+        let exports = fontgarden.export(&all_glyphs, &source_names).unwrap();
+
+        assert_eq!(
+            vec![
+                (
+                    "LightCondensed",
+                    "foreground",
+                    vec!["A", "Aacute", "acute", "comma", "period", "semicolon"]
+                ),
+                ("LightCondensed", "support", vec!["A"]),
+                (
+                    "LightWide",
+                    "foreground",
+                    vec!["A", "Aacute", "acute", "comma", "period", "semicolon"]
+                )
+            ],
+            glyphs_of_exported_fonts(&exports),
+        );
+    }
+
+    #[test]
+    fn export_kerning_subset() {
+        use pretty_assertions::assert_eq;
+
+        let mut fontgarden = Fontgarden::new();
+
+        let font = norad::Font::load("testdata/MutatorSansLightCondensed.ufo").unwrap();
+        let source_name = crate::util::guess_source_name(&font).unwrap();
+        fontgarden
+            .import_with_options(
+                &font,
+                &collect_names!["A", "V"],
+                &name!("Latin"),
+                &source_name,
+                &ImportOptions::default(),
+            )
+            .unwrap();
+        fontgarden
+            .import_with_options(
+                &font,
+                &collect_names!["T"],
+                &name!("default"),
+                &source_name,
+                &ImportOptions::default(),
+            )
+            .unwrap();
+
+        let source_names = collect_names!["LightCondensed"];
+        let exports = fontgarden
+            .export(&collect_names!["A", "T", "V"], &source_names)
+            .unwrap();
+        assert_eq!(font.kerning, exports["LightCondensed"].kerning);
+
+        let exports = fontgarden
+            .export(&collect_names!["A", "V"], &source_names)
+            .unwrap();
+        let kerning = &exports["LightCondensed"].kerning;
+        assert!(!kerning.contains_key("T"));
+        assert_eq!(kerning["V"], font.kerning["V"]);
+        assert_eq!(
+            kerning["public.kern1.@MMK_L_A"],
+            font.kerning["public.kern1.@MMK_L_A"]
+        );
+    }
+
+    #[test]
+    fn export_groups_subset() {
+        use pretty_assertions::assert_eq;
+
+        let mut fontgarden = Fontgarden::new();
+
+        let font = norad::Font::load("testdata/MutatorSansLightCondensed.ufo").unwrap();
+        let source_name = crate::util::guess_source_name(&font).unwrap();
+        fontgarden
+            .import_with_options(
+                &font,
+                &collect_names!["A", "V"],
+                &name!("Latin"),
+                &source_name,
+                &ImportOptions::default(),
+            )
+            .unwrap();
+        fontgarden
+            .import_with_options(
+                &font,
+                &collect_names!["E", "F"],
+                &name!("default"),
+                &source_name,
+                &ImportOptions::default(),
+            )
+            .unwrap();
+
+        assert_eq!(
+            fontgarden.sets["default"].groups,
+            norad::Groups::from([(name!("testGroup"), vec![name!("E"), name!("F")])])
+        );
+
+        let source_names = collect_names!["LightCondensed"];
+        let exports = fontgarden
+            .export(&collect_names!["E", "V"], &source_names)
+            .unwrap();
+        let font = &exports["LightCondensed"];
+        assert_eq!(
+            font.groups,
+            norad::Groups::from([(name!("testGroup"), vec![name!("E")])])
+        );
+        // Pairs referencing the dropped kerning groups go away, too.
+        assert!(font.kerning.is_empty());
+    }
+
+    #[test]
+    fn export_group_member_order() {
+        use pretty_assertions::assert_eq;
+
+        let mut fontgarden = Fontgarden::new();
+
+        let font = norad::Font::load("testdata/MutatorSansLightCondensed.ufo").unwrap();
+        let source_name = crate::util::guess_source_name(&font).unwrap();
+        fontgarden
+            .import_with_options(
+                &font,
+                &collect_names!["F"],
+                &name!("Alpha"),
+                &source_name,
+                &ImportOptions::default(),
+            )
+            .unwrap();
+        fontgarden
+            .import_with_options(
+                &font,
+                &collect_names!["E", "H"],
+                &name!("default"),
+                &source_name,
+                &ImportOptions::default(),
+            )
+            .unwrap();
+
+        let tempdir = tempfile::tempdir().unwrap();
+        fontgarden.save(tempdir.path()).unwrap();
+        let mut fontgarden = Fontgarden::from_path_with_report(tempdir.path()).unwrap().0;
+
+        let source_names = collect_names!["LightCondensed"];
+        let exports = fontgarden
+            .export(&collect_names!["E", "F", "H"], &source_names)
+            .unwrap();
+        assert_eq!(
+            exports["LightCondensed"].groups["testGroup"],
+            vec![name!("E"), name!("F"), name!("H")]
+        );
+
+        // Members that left the group are dropped from the recorded order.
+        let mut font = font;
+        font.groups
+            .get_mut("testGroup")
+            .unwrap()
+            .retain(|name| name != "F");
+        fontgarden
+            .import_with_options(
+                &font,
+                &collect_names!["F"],
+                &name!("Alpha"),
+                &source_name,
+                &ImportOptions::default(),
+            )
+            .unwrap();
+        assert_eq!(
+            fontgarden.group_order["testGroup"],
+            vec![name!("E"), name!("H")]
+        );
+        fontgarden
+            .rename_glyphs(&BTreeMap::from([(name!("E"), name!("E.alt"))]))
+            .unwrap();
+        fontgarden
+            .remove_glyphs(&collect_names!["H"], false)
+            .unwrap();
+        assert_eq!(fontgarden.group_order["testGroup"], vec![name!("E.alt")]);
+        fontgarden
+            .move_glyphs(&collect_names!["E.alt"], &name!("Alpha"), false)
+            .unwrap();
+        assert_eq!(fontgarden.group_order["testGroup"], vec![name!("E.alt")]);
+        fontgarden
+            .remove_glyphs(&collect_names!["E.alt"], false)
+            .unwrap();
+        assert!(fontgarden.group_order.is_empty());
+    }
+
+    #[test]
+    fn export_features() {
+        use pretty_assertions::assert_eq;
+
+        let mut fontgarden = Fontgarden::new();
+
+        let font = norad::Font::load("testdata/MutatorSansLightCondensed.ufo").unwrap();
+        let source_name = crate::util::guess_source_name(&font).unwrap();
+        let sets = [
+            (name!("Latin"), collect_names!["A"]),
+            (name!("Arrows"), collect_names!["arrowleft"]),
+            (name!("Punctuation"), collect_names!["comma"]),
+        ];
+        for (set_name, set_glyphs) in &sets {
+            fontgarden
+                .import_with_options(
+                    &font,
+                    set_glyphs,
+                    set_name,
+                    &source_name,
+                    &ImportOptions::default(),
+                )
+                .unwrap();
+        }
+        // Without any feature code, the one of the UFO becomes the prologue.
+        assert_eq!(fontgarden.features, font.features);
+
+        fontgarden.features = "languagesystem DFLT dflt;".into();
+        fontgarden.sets.get_mut("Latin").unwrap().features = "# Latin\n".into();
+        fontgarden.sets.get_mut("Arrows").unwrap().features = "# Arrows\n".into();
+
+        let report = fontgarden
+            .import_with_options(
+                &font,
+                &collect_names!["A"],
+                &name!("Latin"),
+                &source_name,
+                &ImportOptions::default(),
+            )
+            .unwrap();
+        assert!(report.diverging_features);
+        assert_eq!(fontgarden.features, "languagesystem DFLT dflt;");
+
+        let source_names = collect_names!["LightCondensed"];
+        let exports = fontgarden
+            .export(&collect_names!["A", "arrowleft", "comma"], &source_names)
+            .unwrap();
+        assert_eq!(
+            "languagesystem DFLT dflt;\n# Arrows\n# Latin\n",
+            exports["LightCondensed"].features
+        );
+
+        let exports = fontgarden
+            .export(&collect_names!["A", "comma"], &source_names)
+            .unwrap();
+        assert_eq!(
+            "languagesystem DFLT dflt;\n# Latin\n",
+            exports["LightCondensed"].features
+        );
+    }
+
+    #[test]
+    fn export_glyph_data_lib() {
+        use pretty_assertions::assert_eq;
+
+        let mut fontgarden = Fontgarden::new();
+
+        let mut font = norad::Font::load("testdata/MutatorSansLightCondensed.ufo").unwrap();
+        font.lib.insert(
+            "public.postscriptNames".into(),
+            norad::Plist::from_iter([("A".to_string(), plist::Value::from("uni0041"))]).into(),
+        );
+        font.lib.insert(
+            "public.openTypeCategories".into(),
+            norad::Plist::from_iter([("acute".to_string(), plist::Value::from("mark"))]).into(),
+        );
+        font.lib.insert(
+            "public.skipExportGlyphs".into(),
+            plist::Value::Array(vec![plist::Value::from("A")]),
+        );
+
+        let source_name = crate::util::guess_source_name(&font).unwrap();
+        fontgarden
+            .import_with_options(
+                &font,
+                &collect_names!["Aacute"],
+                &name!("Latin"),
+                &source_name,
+                &ImportOptions::default(),
+            )
+            .unwrap();
+        fontgarden
+            .import_with_options(
+                &font,
+                &collect_names!["B"],
+                &name!("default"),
+                &source_name,
+                &ImportOptions::default(),
+            )
+            .unwrap();
+
+        let source_names = collect_names!["LightCondensed"];
+        let exports = fontgarden
+            .export(&collect_names!["Aacute"], &source_names)
+            .unwrap();
+        let lib = &exports["LightCondensed"].lib;
+
+        assert_eq!(
+            lib["public.glyphOrder"],
+            plist::Value::Array(vec![
+                plist::Value::from("A"),
+                plist::Value::from("Aacute"),
+                plist::Value::from("acute"),
+            ])
+        );
+        for key in [
+            "public.postscriptNames",
+            "public.openTypeCategories",
+            "public.skipExportGlyphs",
+        ] {
+            assert_eq!(lib[key], font.lib[key]);
+        }
+
+        // The UFO glyph order is kept across sets.
+        let exports = fontgarden
+            .export(&collect_names!["Aacute", "B"], &source_names)
+            .unwrap();
+        assert_eq!(
+            exports["LightCondensed"].lib["public.glyphOrder"],
+            plist::Value::Array(vec![
+                plist::Value::from("A"),
+                plist::Value::from("Aacute"),
+                plist::Value::from("B"),
+                plist::Value::from("acute"),
+            ])
+        );
+
+        let tempdir = tempfile::tempdir().unwrap();
+        fontgarden.save(tempdir.path()).unwrap();
+        let fontgarden2 = Fontgarden::from_path_with_report(tempdir.path()).unwrap().0;
+        assert_eq!(fontgarden2.glyph_order, fontgarden.glyph_order);
+
+        // A malformed glyph order is refused.
+        font.lib.insert(
+            "public.glyphOrder".into(),
+            plist::Value::Array(vec![plist::Value::from(1)]),
+        );
+        assert!(matches!(
+            fontgarden.import_with_options(
+                &font,
+                &collect_names!["C"],
+                &name!("default"),
+                &source_name,
+                &ImportOptions::default()
+            ),
+            Err(ImportError::InvalidGlyphOrder)
+        ));
+    }
+
+    #[test]
+    fn codepoints_in_glyph_data() {
+        let mut fontgarden = Fontgarden::new();
+
+        let font = norad::Font::load("testdata/MutatorSansLightCondensed.ufo").unwrap();
+        let source_name = crate::util::guess_source_name(&font).unwrap();
+        fontgarden
+            .import_with_options(
+                &font,
+                &collect_names!["A", "S"],
+                &name!("Latin"),
+                &source_name,
+                &ImportOptions::default(),
+            )
+            .unwrap();
+
+        let set = &fontgarden.sets["Latin"];
+        let source = &set.sources["LightCondensed"];
+        assert_eq!(set.glyph_data["A"].codepoints, Codepoints::new(['A']));
+        assert!(source.layers["foreground"].glyphs["A"]
+            .codepoints
+            .is_empty());
+        // Codepoints in non-default layers are cleared, too, but it is
+        // recorded which glyphs had them. S has none in support.S.wide.
+        assert!(source.layers["support"].glyphs["A"].codepoints.is_empty());
+        assert_eq!(source.layers["support"].encoded, collect_names!["A"]);
+        assert_eq!(source.layers["background"].encoded, collect_names!["S"]);
+        assert!(source.layers["support.S.wide"].encoded.is_empty());
+
+        // Codepoints put into glyphs by hand are not saved in any layer.
+        let mut fontgarden2 = Fontgarden::new();
+        fontgarden2
+            .import_with_options(
+                &font,
+                &collect_names!["A", "S"],
+                &name!("Latin"),
+                &source_name,
+                &ImportOptions::default(),
+            )
+            .unwrap();
+        let source2 = fontgarden2
+            .sets
+            .get_mut("Latin")
+            .unwrap()
+            .sources
+            .get_mut("LightCondensed")
+            .unwrap();
+        for layer in source2.layers.values_mut() {
+            layer.glyphs.get_mut("A").unwrap().codepoints = Codepoints::new(['A']);
+        }
+        let tempdir = tempfile::tempdir().unwrap();
+        fontgarden2.save(tempdir.path()).unwrap();
+        let source_path = tempdir.path().join("set.Latin/source.LightCondensed");
+        for layer_dir in ["glyphs", "glyphs.support"] {
+            let glif =
+                std::fs::read_to_string(source_path.join(layer_dir).join("A_.glif")).unwrap();
+            assert!(!glif.contains("<unicode"));
+        }
+        assert_eq!(
+            std::fs::read_to_string(source_path.join("glyphs.support/encoded_glyphs.csv"))
+                .unwrap()
+                .lines()
+                .collect::<Vec<_>>(),
+            vec!["name", "A"]
+        );
+        assert_eq!(
+            Fontgarden::from_path_with_report(tempdir.path()).unwrap().0,
+            fontgarden
+        );
+
+        // Export puts the codepoints back where they were.
+        let source_names = collect_names!["LightCondensed"];
+        let exports = fontgarden
+            .export(&collect_names!["A", "S"], &source_names)
+            .unwrap();
+        for layer in font.iter_layers() {
+            let exported_layer = exports["LightCondensed"].layers.get(layer.name()).unwrap();
+            for name in ["A", "S"] {
+                if let Some(glyph) = layer.get_glyph(name) {
+                    assert_eq!(
+                        exported_layer.get_glyph(name).unwrap().codepoints,
+                        glyph.codepoints
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn default_layer_normalization() {
+        use pretty_assertions::assert_eq;
+
+        let mut fontgarden = Fontgarden::new();
+
+        let mut font = norad::Font::load("testdata/MutatorSansLightCondensed.ufo").unwrap();
+        let source_name = name!("LightCondensed");
+        fontgarden
+            .import_with_options(
+                &font,
+                &collect_names!["A"],
+                &name!("Latin"),
+                &source_name,
+                &ImportOptions::default(),
+            )
+            .unwrap();
+
+        // A differently named default layer goes into the source's default
+        // layer.
+        font.layers
+            .rename_layer("foreground", "public.default", false)
+            .unwrap();
+        fontgarden
+            .import_with_options(
+                &font,
+                &collect_names!["B"],
+                &name!("default"),
+                &source_name,
+                &ImportOptions::default(),
+            )
+            .unwrap();
+        let source = &fontgarden.sets["default"].sources["LightCondensed"];
+        assert_eq!(source.default_layer_name(), &name!("foreground"));
+        assert!(source.layers["foreground"].glyphs.contains_key("B"));
+
+        let exports = fontgarden
+            .export(&collect_names!["A", "B"], &collect_names!["LightCondensed"])
+            .unwrap();
+        let exported_font = &exports["LightCondensed"];
+        assert_eq!(exported_font.default_layer().name(), &name!("foreground"));
+        assert!(exported_font.default_layer().contains_glyph("A"));
+        assert!(exported_font.default_layer().contains_glyph("B"));
+
+        // A non-default layer of the default layer's name is ambiguous.
+        font.layers
+            .rename_layer("background", "foreground", false)
+            .unwrap();
+        assert!(matches!(
+            fontgarden.import_with_options(
+                &font,
+                &collect_names!["C"],
+                &name!("default"),
+                &source_name,
+                &ImportOptions::default()
+            ),
+            Err(ImportError::DefaultLayerConflict(..))
+        ));
+    }
+
+    #[test]
+    fn images() {
+        use pretty_assertions::assert_eq;
+
+        // Give B an image in a copy of the test font.
+        let tempdir = tempfile::tempdir().unwrap();
+        let ufo_path = tempdir.path().join("Font.ufo");
+        norad::Font::load("testdata/MutatorSansLightCondensed.ufo")
+            .unwrap()
+            .save(&ufo_path)
+            .unwrap();
+        let glif_path = ufo_path.join("glyphs/B_.glif");
+        let glif = std::fs::read_to_string(&glif_path).unwrap();
+        let glif = glif.replacen(
+            "<outline>",
+            "<image fileName=\"image\" xOffset=\"-20\" yOffset=\"-200\"/>\n  <outline>",
+            1,
+        );
+        std::fs::write(&glif_path, glif).unwrap();
+        let font = norad::Font::load(&ufo_path).unwrap();
+
+        // Another source with a different image under the same file name.
+        let mut other_font = font.clone();
+        let other_data = font
+            .images
+            .get(Path::new("image000000000000001"))
+            .unwrap()
+            .unwrap();
+        other_font
+            .images
+            .insert(PathBuf::from("image"), other_data.to_vec())
+            .unwrap();
+
+        let mut fontgarden = Fontgarden::new();
+        let source_name = name!("LightCondensed");
+        let other_source_name = name!("Other");
+        for (font, source_name) in [(&font, &source_name), (&other_font, &other_source_name)] {
+            fontgarden
+                .import_with_options(
+                    font,
+                    &collect_names!["B"],
+                    &name!("Latin"),
+                    source_name,
+                    &ImportOptions::default(),
+                )
+                .unwrap();
+            fontgarden
+                .import_with_options(
+                    font,
+                    &collect_names!["C"],
+                    &name!("default"),
+                    source_name,
+                    &ImportOptions::default(),
+                )
+                .unwrap();
+        }
+
+        let data = font.images.get(Path::new("image")).unwrap().unwrap();
+        let hash = crate::util::content_hash(&data);
+        let other_hash = crate::util::content_hash(&other_data);
+        assert_ne!(hash, other_hash);
+        let latin = &fontgarden.sets["Latin"];
+        assert_eq!(
+            latin.sources["LightCondensed"].images,
+            BTreeMap::from([("image".to_string(), hash.clone())])
+        );
+        assert_eq!(
+            latin.sources["Other"].images,
+            BTreeMap::from([("image".to_string(), other_hash.clone())])
+        );
+        assert!(fontgarden.sets["default"]
+            .sources
+            .values()
+            .all(|source| source.images.is_empty()));
+        assert_eq!(fontgarden.images[&hash], data.to_vec());
+
+        let fontgarden_path = tempdir.path().join("Font.fontgarden");
+        fontgarden.save(&fontgarden_path).unwrap();
+        let fontgarden2 = Fontgarden::from_path_with_report(&fontgarden_path)
+            .unwrap()
+            .0;
+        assert_eq!(fontgarden, fontgarden2);
+
+        // Each source exports its own image.
+        let source_names = collect_names!["LightCondensed", "Other"];
+        let exports = fontgarden
+            .export(&collect_names!["B"], &source_names)
+            .unwrap();
+        for (source_name, data) in [("LightCondensed", &data), ("Other", &other_data)] {
+            let exported_data = exports[source_name]
+                .images
+                .get(Path::new("image"))
+                .unwrap()
+                .unwrap();
+            assert_eq!(exported_data.to_vec(), data.to_vec());
+        }
+
+        let exports = fontgarden
+            .export(&collect_names!["C"], &source_names)
+            .unwrap();
+        assert!(exports["LightCondensed"].images.is_empty());
+
+        // Give C the other image under the same file name in another set. It
+        // is exported under a new file name.
+        let image = fontgarden.sets["Latin"].sources[&source_name]
+            .get_default_layer()
+            .glyphs["B"]
+            .image
+            .clone();
+        let default_source = fontgarden
+            .sets
+            .get_mut("default")
+            .unwrap()
+            .sources
+            .get_mut(&source_name)
+            .unwrap();
+        default_source
+            .get_default_layer_mut()
+            .glyphs
+            .get_mut("C")
+            .unwrap()
+            .image = image;
+        default_source
+            .images
+            .insert("image".to_string(), other_hash.clone());
+        let exports = fontgarden
+            .export(&collect_names!["B", "C"], &collect_names!["LightCondensed"])
+            .unwrap();
+        let ufo = &exports["LightCondensed"];
+        let file_name = |glyph_name: &str| {
+            ufo.default_layer()
+                .get_glyph(glyph_name)
+                .and_then(|glyph| glyph.image.as_ref())
+                .map(|image| image.file_name.clone())
+        };
+        assert_eq!(file_name("B"), Some(PathBuf::from("image")));
+        assert_eq!(file_name("C"), Some(PathBuf::from("image.2")));
+        for (file_name, data) in [("image", &data), ("image.2", &other_data)] {
+            let exported_data = ufo.images.get(Path::new(file_name)).unwrap().unwrap();
+            assert_eq!(exported_data.to_vec(), data.to_vec());
+        }
+    }
+
+    #[test]
+    fn failing_import_changes_nothing() {
+        let tempdir = tempfile::tempdir().unwrap();
+        let font = font_with_broken_image(tempdir.path());
+
+        let mut fontgarden = Fontgarden::new();
+        let result = fontgarden.import_with_options(
+            &font,
+            &collect_names!["B"],
+            &name!("Latin"),
+            &name!("LightCondensed"),
+            &ImportOptions::default(),
+        );
+        assert!(matches!(result, Err(ImportError::LoadImage(..))));
+        assert_eq!(fontgarden, Fontgarden::new());
+    }
+
+    #[test]
+    fn font_lib_and_data() {
+        use pretty_assertions::assert_eq;
+
+        let mut fontgarden = Fontgarden::new();
+
+        let mut fonts = [
+            norad::Font::load("testdata/MutatorSansLightCondensed.ufo").unwrap(),
+            norad::Font::load("testdata/MutatorSansLightWide.ufo").unwrap(),
+        ];
+        fonts[0]
+            .data
+            .insert(
+                PathBuf::from("com.example.tool/settings.txt"),
+                b"condensed".to_vec(),
+            )
+            .unwrap();
+        let owned_key = "com.typesupply.MetricsMachine4.groupColors";
+        let differing_key = "com.typesupply.defcon.sortDescriptor";
+
+        fontgarden
+            .import_with_options(
+                &fonts[0],
+                &collect_names!["A"],
+                &name!("Latin"),
+                &name!("LightCondensed"),
+                &ImportOptions::default(),
+            )
+            .unwrap();
+        // Declare keys as owned by the Latin set.
+        let latin_lib = &mut fontgarden.sets.get_mut("Latin").unwrap().lib;
+        for key in [owned_key, differing_key] {
+            latin_lib.insert(key.into(), plist::Value::Boolean(false));
+        }
+        for font in &fonts {
+            let source_name = crate::util::guess_source_name(font).unwrap();
+            fontgarden
+                .import_with_options(
+                    font,
+                    &collect_names!["A"],
+                    &name!("Latin"),
+                    &source_name,
+                    &ImportOptions::default(),
+                )
+                .unwrap();
+        }
+
+        assert!(fontgarden
+            .lib
+            .contains_key("com.typemytype.robofont.segmentType"));
+        assert!(!fontgarden.lib.contains_key("testLibItemKey"));
+        assert!(fontgarden.sources["LightCondensed"]
+            .lib
+            .contains_key("testLibItemKey"));
+        assert!(!fontgarden.lib.contains_key("public.glyphOrder"));
+        assert_eq!(
+            fontgarden.sets["Latin"].lib.get(owned_key),
+            fonts[1].lib.get(owned_key)
+        );
+        // Values that differ between sources stay with the set's sources.
+        assert!(!fontgarden.sets["Latin"].lib.contains_key(differing_key));
+        for (font, source_name) in fonts.iter().zip(["LightCondensed", "LightWide"]) {
+            assert_eq!(
+                fontgarden.sets["Latin"].sources[source_name]
+                    .lib
+                    .get(differing_key),
+                font.lib.get(differing_key)
+            );
+        }
+
+        let tempdir = tempfile::tempdir().unwrap();
+        fontgarden.save(tempdir.path()).unwrap();
+        std::fs::create_dir_all(tempdir.path().join("sources/Stray")).unwrap();
+        let (fontgarden2, report) = Fontgarden::from_path_with_report(tempdir.path()).unwrap();
+        assert_eq!(fontgarden, fontgarden2);
+        assert_eq!(report.ignored_source_data, vec!["Stray".to_string()]);
+
+        let source_names = collect_names!["LightCondensed", "LightWide"];
+        let exports = fontgarden
+            .export(&collect_names!["A"], &source_names)
+            .unwrap();
+        for (font, source_name) in fonts.iter().zip(["LightCondensed", "LightWide"]) {
+            let exported_font = &exports[source_name];
+            for (key, value) in font.lib.iter() {
+                if key != "public.glyphOrder" && key != owned_key {
+                    assert_eq!(exported_font.lib.get(key), Some(value), "{key}");
+                }
+            }
+            assert!(exported_font.lib.contains_key(owned_key));
+        }
+        let exported_data = exports["LightCondensed"]
+            .data
+            .get(Path::new("com.example.tool/settings.txt"))
+            .unwrap()
+            .unwrap();
+        assert_eq!(exported_data.to_vec(), b"condensed".to_vec());
+        assert!(exports["LightWide"].data.is_empty());
+    }
+
+    #[test]
+    fn metrics_table() {
+        use pretty_assertions::assert_eq;
+
+        let mut fontgarden = Fontgarden::new();
+
+        let fonts = [
+            norad::Font::load("testdata/MutatorSansLightCondensed.ufo").unwrap(),
+            norad::Font::load("testdata/MutatorSansLightWide.ufo").unwrap(),
+        ];
+        for font in &fonts {
+            let source_name = crate::util::guess_source_name(font).unwrap();
+            fontgarden
+                .import_with_options(
+                    font,
+                    &collect_names!["A", "B"],
+                    &name!("Latin"),
+                    &source_name,
+                    &ImportOptions::default(),
+                )
+                .unwrap();
+        }
+
+        // Opt in by creating the file.
+        let tempdir = tempfile::tempdir().unwrap();
+        fontgarden.save(tempdir.path()).unwrap();
+        let metrics_path = tempdir.path().join("set.Latin/metrics.csv");
+        std::fs::write(&metrics_path, "").unwrap();
+        let mut fontgarden = Fontgarden::from_path_with_report(tempdir.path()).unwrap().0;
+
+        let set = &fontgarden.sets["Latin"];
+        assert!(set.metrics);
+        let layer = set.sources["LightCondensed"].get_default_layer();
+        assert_eq!(layer.glyphs["A"].width, 0.0);
+        assert_eq!(
+            layer.metrics["A"].width,
+            fonts[0].default_layer().get_glyph("A").unwrap().width
+        );
+
+        fontgarden.save(tempdir.path()).unwrap();
+        let metrics = std::fs::read_to_string(&metrics_path).unwrap();
+        assert_eq!(
+            metrics.lines().next(),
+            Some(
+                "glyph,width.LightCondensed,height.LightCondensed,width.LightWide,height.LightWide"
+            )
+        );
+        let fontgarden2 = Fontgarden::from_path_with_report(tempdir.path()).unwrap().0;
+        assert_eq!(fontgarden, fontgarden2);
+
+        // The table is authoritative on export.
+        let source = fontgarden
+            .sets
+            .get_mut("Latin")
+            .unwrap()
+            .sources
+            .get_mut("LightWide")
+            .unwrap();
+        source
+            .get_default_layer_mut()
+            .metrics
+            .get_mut("B")
+            .unwrap()
+            .width = 1234.0;
+        let exports = fontgarden
+            .export(&collect_names!["A", "B"], &collect_names!["LightWide"])
+            .unwrap();
+        let layer = exports["LightWide"].default_layer();
+        assert_eq!(layer.get_glyph("B").unwrap().width, 1234.0);
+        assert_eq!(
+            layer.get_glyph("A").unwrap().width,
+            fonts[1].default_layer().get_glyph("A").unwrap().width
+        );
+
+        // Importing into a set with metrics moves the advances, too.
+        fontgarden
+            .import_with_options(
+                &fonts[1],
+                &collect_names!["B"],
+                &name!("Latin"),
+                &name!("LightWide"),
+                &ImportOptions::default(),
+            )
+            .unwrap();
+        let layer = fontgarden.sets["Latin"].sources["LightWide"].get_default_layer();
+        assert_eq!(layer.glyphs["B"].width, 0.0);
+        assert_eq!(
+            layer.metrics["B"].width,
+            fonts[1].default_layer().get_glyph("B").unwrap().width
+        );
+    }
+
+    #[test]
+    fn anchors_table() {
+        use pretty_assertions::assert_eq;
+
+        let mut fontgarden = Fontgarden::new();
+
+        let mut font = norad::Font::load("testdata/MutatorSansLightCondensed.ufo").unwrap();
+        scrub_colors(&mut font);
+        // Plain anchors before an unnamed one stay in the glif to keep the
+        // order.
+        font.layers
+            .get_mut("foreground")
+            .unwrap()
+            .get_glyph_mut("E")
+            .unwrap()
+            .anchors
+            .extend([
+                norad::Anchor::new(207.0, 380.0, None, None, None, None),
+                norad::Anchor::new(207.0, 0.0, Some(name!("bottom")), None, None, None),
+                norad::Anchor::new(400.0, 380.0, Some(name!("right")), None, None, None),
+            ]);
+        let source_name = name!("LightCondensed");
+        fontgarden
+            .import_with_options(
+                &font,
+                &collect_names!["E"],
+                &name!("Latin"),
+                &source_name,
+                &ImportOptions::default(),
+            )
+            .unwrap();
+
+        // Opt in by creating the file.
+        let tempdir = tempfile::tempdir().unwrap();
+        fontgarden.save(tempdir.path()).unwrap();
+        let anchors_path = tempdir.path().join("set.Latin/anchors.csv");
+        std::fs::write(&anchors_path, "").unwrap();
+        let fontgarden = Fontgarden::from_path_with_report(tempdir.path()).unwrap().0;
 
-        let layer_path = if self.default {
-            source_path.join("glyphs")
-        } else {
-            let path = source_path.join(crate::util::default_file_name_for_layer_name(
-                layer_name,
-                existing_layer_names,
-            ));
-            existing_layer_names.insert(path.to_string_lossy().to_string());
-            path
-        };
-        std::fs::create_dir(&layer_path).map_err(SaveLayerError::CreateDir)?;
+        let source = &fontgarden.sets["Latin"].sources["LightCondensed"];
+        assert_eq!(
+            source.layers["foreground"].glyphs["E"]
+                .anchors
+                .iter()
+                .map(|anchor| anchor.name.as_deref())
+                .collect::<Vec<_>>(),
+            vec![Some("top"), None]
+        );
 
-        plist::to_file_xml(
-            layer_path.join("layerinfo.plist"),
-            &LayerInfo {
-                name: layer_name.clone(),
-            },
-        )
-        .map_err(SaveLayerError::WriteLayerInfo)?;
+        fontgarden.save(tempdir.path()).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&anchors_path)
+                .unwrap()
+                .lines()
+                .collect::<Vec<_>>(),
+            vec![
+                "glyph,source,layer,name,x,y",
+                "E,LightCondensed,foreground,bottom,207,0",
+                "E,LightCondensed,foreground,right,400,380",
+                "E,LightCondensed,support.crossbar,top,282.15144,813.99416",
+            ]
+        );
+        let fontgarden2 = Fontgarden::from_path_with_report(tempdir.path()).unwrap().0;
+        assert_eq!(fontgarden, fontgarden2);
 
-        let mut existing_glyph_names = HashSet::new();
-        for (glyph_name, glyph) in &self.glyphs {
-            let filename =
-                crate::util::default_file_name_for_glyph_name(glyph_name, &existing_glyph_names);
-            let glyph_path = layer_path.join(&filename);
-            glyph
-                .save(&glyph_path)
-                .map_err(|e| SaveLayerError::SaveGlyph(glyph_name.clone(), e))?;
-            existing_glyph_names.insert(filename.to_string_lossy().to_string());
+        let exports = fontgarden
+            .export(&collect_names!["E"], &collect_names!["LightCondensed"])
+            .unwrap();
+        for layer_name in ["foreground", "support.crossbar"] {
+            let reference = font.layers.get(layer_name).unwrap().get_glyph("E").unwrap();
+            let exported = exports["LightCondensed"]
+                .layers
+                .get(layer_name)
+                .unwrap()
+                .get_glyph("E")
+                .unwrap();
+            assert_glyph_eq(reference, exported);
         }
+    }
 
-        Self::write_color_marks(&layer_path.join("color_marks.csv"), &self.color_marks)
-            .map_err(SaveLayerError::WriteColorMarks)?;
+    #[test]
+    fn components_table() {
+        use pretty_assertions::assert_eq;
 
-        Ok(())
+        let mut fontgarden = Fontgarden::new();
+
+        let font = norad::Font::load("testdata/MutatorSansLightCondensed.ufo").unwrap();
+        let source_name = name!("LightCondensed");
+        fontgarden
+            .import_with_options(
+                &font,
+                &collect_names!["A"],
+                &name!("default"),
+                &source_name,
+                &ImportOptions::default(),
+            )
+            .unwrap();
+        fontgarden
+            .import_with_options(
+                &font,
+                &collect_names!["Aacute"],
+                &name!("Latin"),
+                &source_name,
+                &ImportOptions::default(),
+            )
+            .unwrap();
+
+        let tempdir = tempfile::tempdir().unwrap();
+        fontgarden.save(tempdir.path()).unwrap();
+        let components_path = tempdir.path().join("set.Latin/components.csv");
+        assert_eq!(
+            std::fs::read_to_string(&components_path)
+                .unwrap()
+                .lines()
+                .collect::<Vec<_>>(),
+            vec![
+                "glyph,base,base_set",
+                "Aacute,A,default",
+                "Aacute,acute,Latin"
+            ]
+        );
+        assert!(!tempdir.path().join("set.default/components.csv").exists());
+        Fontgarden::from_path_with_report(tempdir.path()).unwrap();
+
+        std::fs::write(&components_path, "glyph,base,base_set\nAacute,A,default\n").unwrap();
+        assert!(matches!(
+            Fontgarden::from_path_with_report(tempdir.path()),
+            Err(LoadError::ComponentsMismatch(set, glyph, base))
+                if set == name!("Latin") && glyph == name!("Aacute") && base == name!("acute")
+        ));
+
+        // Deleting the stale table lets the Fontgarden load again.
+        std::fs::remove_file(&components_path).unwrap();
+        assert_eq!(
+            Fontgarden::from_path_with_report(tempdir.path()).unwrap().0,
+            fontgarden
+        );
     }
 
-    fn write_color_marks(
-        path: &Path,
-        color_marks: &BTreeMap<Name, Color>,
-    ) -> Result<(), csv::Error> {
-        let mut writer = csv::Writer::from_path(&path)?;
+    #[test]
+    fn shared_glyphs() {
+        use pretty_assertions::assert_eq;
 
-        writer.write_record(&["name", "color"])?;
-        for (name, color) in color_marks {
-            writer.serialize((name, color))?;
-        }
-        writer.flush()?;
+        let mut fontgarden = Fontgarden::new();
 
-        Ok(())
-    }
-}
+        let font = norad::Font::load("testdata/MutatorSansLightCondensed.ufo").unwrap();
+        let set_name = name!("Latin");
+        fontgarden
+            .import_with_options(
+                &font,
+                &collect_names!["A", "B"],
+                &set_name,
+                &name!("One"),
+                &ImportOptions::default(),
+            )
+            .unwrap();
+        fontgarden
+            .import_with_options(
+                &font,
+                &collect_names!["A"],
+                &set_name,
+                &name!("Two"),
+                &ImportOptions::default(),
+            )
+            .unwrap();
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+        let tempdir = tempfile::tempdir().unwrap();
+        fontgarden.save(tempdir.path()).unwrap();
 
-    macro_rules! collect_names {
-        ($($x: expr), + $(,) ?) => {
-            [$($x),+].iter().map(|n| Name::new(n).unwrap()).collect()
+        // A is identical in both sources, in the foreground and support
+        // layers, B only exists in one source.
+        let set_path = tempdir.path().join("set.Latin");
+        let file_names = |path: PathBuf| {
+            let mut names: Vec<String> = std::fs::read_dir(path)
+                .unwrap()
+                .map(|e| e.unwrap().file_name().to_string_lossy().to_string())
+                .filter(|n| n.ends_with(".glif"))
+                .collect();
+            names.sort();
+            names
         };
+        assert_eq!(file_names(set_path.join("shared_glyphs")).len(), 2);
+        assert_eq!(
+            file_names(set_path.join("source.One/glyphs")),
+            vec!["B_.glif".to_string()]
+        );
+        assert!(file_names(set_path.join("source.Two/glyphs")).is_empty());
+        assert!(set_path
+            .join("source.Two/glyphs/shared_glyphs.csv")
+            .exists());
+
+        let fontgarden2 = Fontgarden::from_path_with_report(tempdir.path()).unwrap().0;
+        assert_eq!(fontgarden, fontgarden2);
+
+        // A row pointing at a shared glyph of another name must not load.
+        let csv_path = set_path.join("source.Two/glyphs/shared_glyphs.csv");
+        let csv = std::fs::read_to_string(&csv_path).unwrap();
+        std::fs::write(&csv_path, csv.replace("\nA,", "\nB,")).unwrap();
+        assert!(Fontgarden::from_path_with_report(tempdir.path()).is_err());
     }
 
-    macro_rules! name {
-        ($e: expr) => {
-            Name::new($e).unwrap()
+    #[test]
+    fn shared_glyphs_hash_collision() {
+        let font = norad::Font::load("testdata/MutatorSansLightCondensed.ufo").unwrap();
+        let options = LayerImportOptions {
+            default: true,
+            ..Default::default()
         };
+        let layer = Layer::from_ufo_layer(font.default_layer(), &collect_names!["A"], options);
+
+        // Pretend that a different shared glif has the same hash as A.
+        let data = layer.glyphs["A"].encode_xml().unwrap();
+        let hash = crate::util::content_hash(&data);
+        let glifs = BTreeMap::from([(name!("A"), (hash.clone(), data.clone()))]);
+        let shared_glyphs = BTreeMap::from([(hash.as_str(), b"<glyph/>".as_slice())]);
+
+        let tempdir = tempfile::tempdir().unwrap();
+        layer
+            .save(
+                &name!("public.default"),
+                tempdir.path(),
+                &mut HashSet::new(),
+                &glifs,
+                &shared_glyphs,
+            )
+            .unwrap();
+
+        let layer_path = tempdir.path().join("glyphs");
+        assert_eq!(std::fs::read(layer_path.join("A_.glif")).unwrap(), data);
+        assert!(!layer_path.join("shared_glyphs.csv").exists());
     }
 
     #[test]
-    fn load_empty() {
-        let tempdir = tempfile::TempDir::new().unwrap();
+    fn diverging_components() {
+        use pretty_assertions::assert_eq;
 
-        let fontgarden = Fontgarden::new();
-        fontgarden.save(tempdir.path()).unwrap();
-        let fontgarden2 = Fontgarden::from_path(tempdir.path()).unwrap();
+        let mut fontgarden = Fontgarden::new();
 
-        assert_eq!(fontgarden, fontgarden2);
+        let font = norad::Font::load("testdata/MutatorSansLightCondensed.ufo").unwrap();
+        let set_name = name!("Latin");
+        let source_name = name!("LightCondensed");
+        fontgarden
+            .import_with_options(
+                &font,
+                &collect_names!["A"],
+                &set_name,
+                &source_name,
+                &ImportOptions::default(),
+            )
+            .unwrap();
+
+        let mut changed_font = font.clone();
+        changed_font
+            .default_layer_mut()
+            .get_glyph_mut("A")
+            .unwrap()
+            .width += 10.0;
+        let glyphs = collect_names!["Aacute"];
+
+        assert!(matches!(
+            fontgarden.import_with_options(&changed_font, &glyphs, &set_name, &source_name, &ImportOptions::default()),
+            Err(ImportError::DivergingComponents(source, glyphs))
+                if source == source_name && glyphs == vec![name!("A")]
+        ));
+        assert!(!fontgarden.sets["Latin"].glyph_coverage().contains("Aacute"));
+
+        let options = ImportOptions {
+            divergence: DivergencePolicy::KeepStored,
+            ..Default::default()
+        };
+        let report = fontgarden
+            .import_with_options(&changed_font, &glyphs, &set_name, &source_name, &options)
+            .unwrap();
+        assert_eq!(report.diverging_components, vec![name!("A")]);
+        let layer = fontgarden.sets["Latin"].sources["LightCondensed"].get_default_layer();
+        assert!(layer.glyphs.contains_key("Aacute"));
+        assert_eq!(
+            layer.glyphs["A"].width,
+            font.default_layer().get_glyph("A").unwrap().width
+        );
+
+        let options = ImportOptions {
+            divergence: DivergencePolicy::Warn,
+            ..Default::default()
+        };
+        let report = fontgarden
+            .import_with_options(&changed_font, &glyphs, &set_name, &source_name, &options)
+            .unwrap();
+        assert_eq!(report.diverging_components, vec![name!("A")]);
+        let layer = fontgarden.sets["Latin"].sources["LightCondensed"].get_default_layer();
+        assert_eq!(
+            layer.glyphs["A"].width,
+            changed_font.default_layer().get_glyph("A").unwrap().width
+        );
     }
 
     #[test]
-    fn save_load() {
+    fn import_policies() {
+        use pretty_assertions::assert_eq;
+
+        let mut fontgarden = Fontgarden::new();
+
+        let font = norad::Font::load("testdata/MutatorSansLightCondensed.ufo").unwrap();
+        let source_name = name!("LightCondensed");
+        fontgarden
+            .import_with_options(
+                &font,
+                &collect_names!["A"],
+                &name!("Latin"),
+                &source_name,
+                &ImportOptions::default(),
+            )
+            .unwrap();
+        fontgarden
+            .import_with_options(
+                &font,
+                &collect_names!["arrowleft"],
+                &name!("default"),
+                &source_name,
+                &ImportOptions::default(),
+            )
+            .unwrap();
+
+        let mut changed_font = font.clone();
+        changed_font
+            .default_layer_mut()
+            .get_glyph_mut("A")
+            .unwrap()
+            .width += 10.0;
+        changed_font.font_info.copyright = Some("Changed".into());
+        let stored_width = |fontgarden: &Fontgarden| {
+            fontgarden.sets["Latin"].sources["LightCondensed"]
+                .get_default_layer()
+                .glyphs["A"]
+                .width
+        };
+        let original_width = stored_width(&fontgarden);
+
+        // Glyphs of other sets are refused, components of other sets skipped.
+        let options = ImportOptions {
+            only_sets: Some(collect_names!["default"]),
+            ..Default::default()
+        };
+        assert!(matches!(
+            fontgarden.import_with_options(
+                &changed_font,
+                &collect_names!["A", "arrowright"],
+                &name!("default"),
+                &source_name,
+                &options,
+            ),
+            Err(ImportError::GlyphsInOtherSets(glyphs)) if glyphs == vec![name!("A")]
+        ));
+        let report = fontgarden
+            .import_with_options(
+                &changed_font,
+                &collect_names!["Aacute"],
+                &name!("default"),
+                &source_name,
+                &options,
+            )
+            .unwrap();
+        assert_eq!(report.modified_sets, collect_names!["default"]);
+        assert_eq!(stored_width(&fontgarden), original_width);
+        assert!(fontgarden.sets["default"]
+            .glyph_coverage()
+            .contains("acute"));
+
+        let options = ImportOptions {
+            mode: ImportMode::AddNewOnly,
+            ..Default::default()
+        };
+        let report = fontgarden
+            .import_with_options(
+                &changed_font,
+                &collect_names!["A", "B"],
+                &name!("Latin"),
+                &source_name,
+                &options,
+            )
+            .unwrap();
+        assert_eq!(report.modified_sets, collect_names!["Latin"]);
+        assert_eq!(stored_width(&fontgarden), original_width);
+        assert!(fontgarden.sets["Latin"].glyph_coverage().contains("B"));
+
+        let options = ImportOptions {
+            mode: ImportMode::UpdateExisting,
+            ..Default::default()
+        };
+        let report = fontgarden
+            .import_with_options(
+                &changed_font,
+                &collect_names!["A", "C"],
+                &name!("Latin"),
+                &source_name,
+                &options,
+            )
+            .unwrap();
+        assert_eq!(report.modified_sets, collect_names!["Latin"]);
+        assert_ne!(stored_width(&fontgarden), original_width);
+        assert!(!fontgarden.sets["Latin"].glyph_coverage().contains("C"));
+
+        // Restricted imports leave the font info alone, others take it over.
+        assert!(!report.updated_font_data);
+        assert_eq!(fontgarden.font_info["LightCondensed"], font.font_info);
+        let report = fontgarden
+            .import_with_options(
+                &changed_font,
+                &collect_names!["A"],
+                &name!("Latin"),
+                &source_name,
+                &ImportOptions::default(),
+            )
+            .unwrap();
+        assert!(report.updated_font_data);
+        assert_eq!(
+            fontgarden.font_info["LightCondensed"],
+            changed_font.font_info
+        );
+    }
+
+    #[test]
+    fn move_glyphs() {
+        use pretty_assertions::assert_eq;
+
         let mut fontgarden = Fontgarden::new();
+        fontgarden.sets.insert(
+            name!("default"),
+            Set {
+                metrics: true,
+                anchors: true,
+                ..Default::default()
+            },
+        );
 
         let fonts = [
             norad::Font::load("testdata/MutatorSansLightWide.ufo").unwrap(),
             norad::Font::load("testdata/MutatorSansLightCondensed.ufo").unwrap(),
         ];
-
-        let sets = vec![
-            (name!("Latin"), collect_names!["A", "Aacute", "S"]),
-            (
-                name!("Arrows"),
-                collect_names!["quotedblbase", "quotedblleft", "comma"],
-            ),
-            (name!("Punctuation"), collect_names!["arrowleft"]),
-            (name!("default"), collect_names!["acute"]),
-        ];
-
         for font in &fonts {
             let source_name = crate::util::guess_source_name(font).unwrap();
-            for (set_name, set_glyphs) in &sets {
-                fontgarden
-                    .import(font, set_glyphs, set_name, &source_name)
-                    .unwrap();
-            }
+            fontgarden
+                .import_with_options(
+                    font,
+                    &collect_names!["A", "Aacute", "Adieresis", "B"],
+                    &name!("default"),
+                    &source_name,
+                    &ImportOptions::default(),
+                )
+                .unwrap();
         }
+        let reference_exports = fontgarden
+            .export(
+                &collect_names!["A", "Aacute", "Adieresis", "B", "acute", "dieresis", "dot"],
+                &collect_names!["LightWide", "LightCondensed"],
+            )
+            .unwrap();
+
+        assert!(matches!(
+            fontgarden.move_glyphs(&collect_names!["Z"], &name!("Latin"), false),
+            Err(MoveError::UnknownGlyphs(glyphs)) if glyphs == vec![name!("Z")]
+        ));
+
+        // The dieresis and the nested dot are only used by Adieresis, the A
+        // is also used by Aacute.
+        let moved = fontgarden
+            .move_glyphs(&collect_names!["Adieresis"], &name!("Latin"), true)
+            .unwrap();
+        assert_eq!(moved, collect_names!["Adieresis", "dieresis", "dot"]);
+        assert_eq!(
+            vec![
+                (
+                    "Latin",
+                    "LightCondensed",
+                    "foreground",
+                    vec!["Adieresis", "dieresis", "dot"],
+                ),
+                (
+                    "Latin",
+                    "LightWide",
+                    "foreground",
+                    vec!["Adieresis", "dieresis", "dot"],
+                ),
+                (
+                    "default",
+                    "LightCondensed",
+                    "foreground",
+                    vec!["A", "Aacute", "B", "acute"],
+                ),
+                ("default", "LightCondensed", "support", vec!["A"]),
+                ("default", "LightCondensed", "support.crossbar", vec!["B"]),
+                (
+                    "default",
+                    "LightWide",
+                    "foreground",
+                    vec!["A", "Aacute", "B", "acute"],
+                ),
+            ],
+            glyphs_of_fontgarden(&fontgarden),
+        );
+        assert!(fontgarden.sets["Latin"]
+            .glyph_data
+            .contains_key("Adieresis"));
+        assert!(!fontgarden.sets["default"]
+            .glyph_data
+            .contains_key("Adieresis"));
+        // The new set keeps the tables of the set the glyphs came from.
+        assert!(fontgarden.sets["Latin"].metrics);
+        assert!(fontgarden.sets["Latin"].anchors);
+
+        // Moving everything out of a set leaves it empty.
+        fontgarden
+            .move_glyphs(
+                &collect_names!["A", "Aacute", "B", "acute"],
+                &name!("Latin"),
+                false,
+            )
+            .unwrap();
+        assert!(fontgarden.sets["default"].glyph_coverage().is_empty());
 
-        for set in fontgarden.sets.values() {
-            for source in set.sources.values() {
-                for (layer_name, layer) in &source.layers {
-                    if layer_name.as_ref() == "foreground" {
-                        assert!(layer.default)
-                    } else {
-                        assert!(!layer.default)
-                    }
-                }
-            }
+        let tempdir = tempfile::tempdir().unwrap();
+        fontgarden.save(tempdir.path()).unwrap();
+        let fontgarden2 = Fontgarden::from_path_with_report(tempdir.path()).unwrap().0;
+        assert_eq!(fontgarden, fontgarden2);
+
+        let exports = fontgarden
+            .export(
+                &collect_names!["A", "Aacute", "Adieresis", "B", "acute", "dieresis", "dot"],
+                &collect_names!["LightWide", "LightCondensed"],
+            )
+            .unwrap();
+        for (source_name, reference) in &reference_exports {
+            assert_font_eq(reference, &exports[source_name]);
         }
+    }
+
+    #[test]
+    fn rename_glyphs() {
+        use pretty_assertions::assert_eq;
+
+        let mut fontgarden = Fontgarden::new();
+
+        let font = norad::Font::load("testdata/MutatorSansLightWide.ufo").unwrap();
+        let source_name = name!("LightWide");
+        fontgarden
+            .import_with_options(
+                &font,
+                &collect_names!["A"],
+                &name!("Latin"),
+                &source_name,
+                &ImportOptions::default(),
+            )
+            .unwrap();
+        fontgarden
+            .import_with_options(
+                &font,
+                &collect_names!["Aacute", "V"],
+                &name!("Accented"),
+                &source_name,
+                &ImportOptions::default(),
+            )
+            .unwrap();
+
+        let renames = BTreeMap::from([(name!("A"), name!("A.ss01"))]);
+        assert!(matches!(
+            fontgarden.rename_glyphs(&BTreeMap::from([(name!("Z"), name!("Z.ss01"))])),
+            Err(RenameError::UnknownGlyph(glyph)) if glyph == name!("Z")
+        ));
+        assert!(matches!(
+            fontgarden.rename_glyphs(&BTreeMap::from([(name!("A"), name!("V"))])),
+            Err(RenameError::NameCollision(old, new)) if old == name!("A") && new == name!("V")
+        ));
+        fontgarden.features = "@UC = [A Aacute V];\n".into();
+        fontgarden.sets.get_mut("Latin").unwrap().features =
+            "# A stays here\nsub A.alt A by A_A;\nsub @A' V by A;\n".into();
+        fontgarden.rename_glyphs(&renames).unwrap();
+        assert_eq!(fontgarden.features, "@UC = [A.ss01 Aacute V];\n");
+        assert_eq!(
+            fontgarden.sets["Latin"].features,
+            "# A stays here\nsub A.alt A.ss01 by A_A;\nsub @A' V by A.ss01;\n"
+        );
+
+        let latin = &fontgarden.sets["Latin"];
+        assert!(latin.glyph_data.contains_key("A.ss01"));
+        assert!(!latin.glyph_data.contains_key("A"));
+        let layer = latin.sources["LightWide"].get_default_layer();
+        assert_eq!(layer.glyphs["A.ss01"].name().as_str(), "A.ss01");
+        assert_eq!(latin.groups["public.kern1.@MMK_L_A"], vec![name!("A.ss01")]);
+
+        let layer = fontgarden.sets["Accented"].sources["LightWide"].get_default_layer();
+        let bases: Vec<&str> = layer.glyphs["Aacute"]
+            .components
+            .iter()
+            .map(|c| c.base.as_str())
+            .collect();
+        assert_eq!(bases, vec!["A.ss01", "acute"]);
+
+        let tempdir = tempfile::tempdir().unwrap();
+        fontgarden.save(tempdir.path()).unwrap();
+        let fontgarden2 = Fontgarden::from_path_with_report(tempdir.path()).unwrap().0;
+        assert_eq!(fontgarden, fontgarden2);
+    }
+
+    #[test]
+    fn remove_glyphs() {
+        use pretty_assertions::assert_eq;
+
+        let mut fontgarden = Fontgarden::new();
+
+        let font = norad::Font::load("testdata/MutatorSansLightWide.ufo").unwrap();
+        let source_name = name!("LightWide");
+        fontgarden
+            .import_with_options(
+                &font,
+                &collect_names!["Adieresis", "B"],
+                &name!("Latin"),
+                &source_name,
+                &ImportOptions::default(),
+            )
+            .unwrap();
+
+        // The dot is used through the dieresis.
+        assert!(matches!(
+            fontgarden.remove_glyphs(&collect_names!["dot"], false),
+            Err(RemoveError::UsedAsComponents(dependents))
+                if dependents == BTreeMap::from([
+                    (name!("Adieresis"), collect_names!["dot"]),
+                    (name!("dieresis"), collect_names!["dot"]),
+                ])
+        ));
+
+        fontgarden
+            .remove_glyphs(&collect_names!["Adieresis", "dieresis", "dot"], false)
+            .unwrap();
+        assert_eq!(
+            vec![("Latin", "LightWide", "foreground", vec!["A", "B"])],
+            glyphs_of_fontgarden(&fontgarden),
+        );
+        assert!(!fontgarden.sets["Latin"]
+            .glyph_data
+            .contains_key("Adieresis"));
+
+        fontgarden
+            .remove_glyphs(&collect_names!["A"], true)
+            .unwrap();
+        let source = &fontgarden.sets["Latin"].sources["LightWide"];
+        assert!(!source.kerning.contains_key("public.kern1.@MMK_L_A"));
+        assert!(!source.kerning["B"].contains_key("public.kern2.@MMK_R_A"));
+        assert!(source
+            .kerning
+            .values()
+            .all(|seconds| !seconds.contains_key("A")));
+        assert!(!fontgarden.sets["Latin"]
+            .groups
+            .contains_key("public.kern1.@MMK_L_A"));
+    }
+
+    #[test]
+    fn manage_sets() {
+        use pretty_assertions::assert_eq;
+
+        let mut fontgarden = Fontgarden::new();
+
+        let font = norad::Font::load("testdata/MutatorSansLightWide.ufo").unwrap();
+        fontgarden
+            .import_with_options(
+                &font,
+                &collect_names!["A", "B", "arrowleft"],
+                &name!("default"),
+                &name!("LightWide"),
+                &ImportOptions::default(),
+            )
+            .unwrap();
+        let coverage = |fontgarden: &Fontgarden, set_name: &str| -> BTreeSet<Name> {
+            fontgarden.sets[set_name]
+                .glyph_coverage()
+                .into_iter()
+                .collect()
+        };
+
+        fontgarden.new_set(&name!("Empty")).unwrap();
+        assert!(matches!(
+            fontgarden.new_set(&name!("default")),
+            Err(SetError::SetExists(set)) if set == name!("default")
+        ));
+
+        let latin = fontgarden.sets["default"]
+            .glyphs_of_script("latin")
+            .unwrap();
+        assert_eq!(latin, collect_names!["A", "B"]);
+        assert_eq!(
+            fontgarden.sets["default"].glyphs_of_script("Latn"),
+            Some(latin.clone())
+        );
+        assert!(fontgarden.sets["default"]
+            .glyphs_of_script("Klingon")
+            .is_none());
+        assert!(matches!(
+            fontgarden.split_set(&name!("default"), &name!("Latin"), &collect_names!["Z"]),
+            Err(SetError::GlyphsNotInSet(set, glyphs))
+                if set == name!("default") && glyphs == vec![name!("Z")]
+        ));
+        fontgarden
+            .split_set(&name!("default"), &name!("Latin"), &latin)
+            .unwrap();
+        assert_eq!(coverage(&fontgarden, "Latin"), collect_names!["A", "B"]);
+        assert_eq!(
+            coverage(&fontgarden, "default"),
+            collect_names!["arrowleft"]
+        );
+
+        fontgarden
+            .rename_set(&name!("Latin"), &name!("Latn"))
+            .unwrap();
+        assert!(!fontgarden.sets.contains_key("Latin"));
+
+        fontgarden.sets.get_mut("default").unwrap().features = "# Arrows\n".into();
+        fontgarden
+            .merge_sets(&name!("default"), &name!("Latn"))
+            .unwrap();
+        assert!(!fontgarden.sets.contains_key("default"));
+        assert_eq!(
+            coverage(&fontgarden, "Latn"),
+            collect_names!["A", "B", "arrowleft"]
+        );
+        assert_eq!(fontgarden.sets["Latn"].features, "# Arrows\n");
+
+        assert!(matches!(
+            fontgarden.delete_set(&name!("Latn"), None),
+            Err(SetError::NotEmpty(set, 3)) if set == name!("Latn")
+        ));
+        fontgarden
+            .delete_set(&name!("Latn"), Some(&name!("Empty")))
+            .unwrap();
+        assert_eq!(
+            coverage(&fontgarden, "Empty"),
+            collect_names!["A", "B", "arrowleft"]
+        );
+        fontgarden.new_set(&name!("Other")).unwrap();
+        fontgarden.sets.get_mut("Other").unwrap().features = "# Other\n".into();
+        assert!(matches!(
+            fontgarden.delete_set(&name!("Other"), None),
+            Err(SetError::HasFeaturesOrLib(set)) if set == name!("Other")
+        ));
+        fontgarden.sets.get_mut("Other").unwrap().features.clear();
+        fontgarden.delete_set(&name!("Other"), None).unwrap();
+        assert_eq!(
+            fontgarden.sets.keys().collect::<Vec<_>>(),
+            vec![&name!("Empty")]
+        );
+
+        let tempdir = tempfile::tempdir().unwrap();
+        fontgarden.save(tempdir.path()).unwrap();
+        let fontgarden2 = Fontgarden::from_path_with_report(tempdir.path()).unwrap().0;
+        assert_eq!(fontgarden, fontgarden2);
+    }
+
+    #[test]
+    fn manage_sources() {
+        use pretty_assertions::assert_eq;
+
+        let mut fontgarden = Fontgarden::new();
+
+        let font = norad::Font::load("testdata/MutatorSansLightWide.ufo").unwrap();
+        let source_name = name!("LightWide");
+        fontgarden
+            .import_with_options(
+                &font,
+                &collect_names!["A", "B"],
+                &name!("Latin"),
+                &source_name,
+                &ImportOptions::default(),
+            )
+            .unwrap();
+        fontgarden
+            .import_with_options(
+                &font,
+                &collect_names!["arrowleft"],
+                &name!("default"),
+                &source_name,
+                &ImportOptions::default(),
+            )
+            .unwrap();
+
+        // Only glyphs already in the Fontgarden are imported.
+        let new_font = norad::Font::load("testdata/MutatorSansLightCondensed.ufo").unwrap();
+        let new_source_name = name!("LightCondensed");
+        fontgarden.designspace.axes.push(crate::designspace::Axis {
+            name: "width".into(),
+            tag: "wdth".into(),
+            minimum: Some(0.0),
+            default: 0.0,
+            maximum: Some(1000.0),
+            hidden: false,
+            map: Vec::new(),
+        });
+        assert!(matches!(
+            fontgarden.add_source(
+                &new_font,
+                &new_source_name,
+                Location::from([("weight".into(), 0.0)])
+            ),
+            Err(SourceError::UnknownAxis(axis)) if axis == "weight"
+        ));
+        assert!(!fontgarden.sources.contains_key("LightCondensed"));
+        let report = fontgarden
+            .add_source(
+                &new_font,
+                &new_source_name,
+                Location::from([("width".into(), 0.0)]),
+            )
+            .unwrap();
+        assert_eq!(report.modified_sets, collect_names!["Latin", "default"]);
+        assert_eq!(
+            fontgarden.sources["LightCondensed"].location,
+            Location::from([("width".into(), 0.0)])
+        );
+        assert_eq!(
+            vec![
+                ("Latin", "LightCondensed", "foreground", vec!["A", "B"]),
+                ("Latin", "LightCondensed", "support", vec!["A"]),
+                ("Latin", "LightCondensed", "support.crossbar", vec!["B"]),
+                ("Latin", "LightWide", "foreground", vec!["A", "B"]),
+                ("default", "LightCondensed", "foreground", vec!["arrowleft"]),
+                ("default", "LightWide", "foreground", vec!["arrowleft"]),
+            ],
+            glyphs_of_fontgarden(&fontgarden),
+        );
+        assert!(matches!(
+            fontgarden.add_source(&new_font, &new_source_name, Location::new()),
+            Err(SourceError::SourceExists(source)) if source == new_source_name
+        ));
+
+        // A copy needs a location of its own, and takes the layer sources of
+        // the original along.
+        fontgarden.designspace.layer_sources.insert(
+            name!("LightCondensed.support"),
+            crate::designspace::LayerSource {
+                source: new_source_name.clone(),
+                layer: name!("support"),
+                location: Location::from([("width".into(), 100.0)]),
+            },
+        );
+        assert!(matches!(
+            fontgarden.duplicate_source(&new_source_name, &name!("Copy"), Location::new()),
+            Err(SourceError::SameLocation(source, _)) if source == name!("Copy")
+        ));
+        fontgarden
+            .duplicate_source(
+                &new_source_name,
+                &name!("Copy"),
+                Location::from([("width".into(), 500.0)]),
+            )
+            .unwrap();
+        assert_eq!(fontgarden.sources["Copy"].filename, "Copy");
+        assert_eq!(
+            fontgarden.designspace.layer_sources["Copy.support"],
+            crate::designspace::LayerSource {
+                source: name!("Copy"),
+                layer: name!("support"),
+                location: Location::from([("width".into(), 600.0)]),
+            }
+        );
+        assert_eq!(
+            fontgarden.sets["Latin"].sources["Copy"],
+            fontgarden.sets["Latin"].sources["LightCondensed"]
+        );
+
+        fontgarden
+            .rename_source(&name!("Copy"), &name!("Renamed"))
+            .unwrap();
+        assert!(!fontgarden.sources.contains_key("Copy"));
+        assert!(fontgarden.font_info.contains_key("Renamed"));
+        assert!(fontgarden.sets["default"].sources.contains_key("Renamed"));
+
+        fontgarden.remove_source(&name!("Renamed")).unwrap();
+        assert!(!fontgarden.sources.contains_key("Renamed"));
+        assert!(!fontgarden.font_info.contains_key("Renamed"));
+        assert!(fontgarden
+            .sets
+            .values()
+            .all(|set| !set.sources.contains_key("Renamed")));
+        assert!(matches!(
+            fontgarden.remove_source(&name!("Renamed")),
+            Err(SourceError::UnknownSource(source)) if source == name!("Renamed")
+        ));
 
         let tempdir = tempfile::tempdir().unwrap();
         fontgarden.save(tempdir.path()).unwrap();
-        let fontgarden2 = Fontgarden::from_path(tempdir.path()).unwrap();
-
-        // TODO: write per-field assert_eq for easier diffs.
+        let fontgarden2 = Fontgarden::from_path_with_report(tempdir.path()).unwrap().0;
         assert_eq!(fontgarden, fontgarden2);
     }
 
     #[test]
-    fn export_import() {
+    fn add_source_failure_and_skipped_glyphs() {
+        use pretty_assertions::assert_eq;
+
         let mut fontgarden = Fontgarden::new();
 
-        let mut fonts = [
-            norad::Font::load("testdata/MutatorSansLightWide.ufo").unwrap(),
-            norad::Font::load("testdata/MutatorSansLightCondensed.ufo").unwrap(),
-        ];
-        for font in &mut fonts {
-            scrub_colors(font);
-        }
-        let all_glyphs: HashSet<Name> = fonts[0].iter_names().collect();
+        let font = norad::Font::load("testdata/MutatorSansLightWide.ufo").unwrap();
+        fontgarden
+            .import_with_options(
+                &font,
+                &collect_names!["A", "B"],
+                &name!("Latin"),
+                &name!("LightWide"),
+                &ImportOptions::default(),
+            )
+            .unwrap();
+
+        // A failing import leaves no trace of the new source.
+        let tempdir = tempfile::tempdir().unwrap();
+        let new_font = font_with_broken_image(tempdir.path());
+        let new_source_name = name!("LightCondensed");
+        assert!(matches!(
+            fontgarden.add_source(&new_font, &new_source_name, Location::new()),
+            Err(SourceError::Import(ImportError::LoadImage(..)))
+        ));
+        assert!(!fontgarden.sources.contains_key("LightCondensed"));
+        assert!(!fontgarden.font_info.contains_key("LightCondensed"));
+        assert!(fontgarden
+            .sets
+            .values()
+            .all(|set| !set.sources.contains_key("LightCondensed")));
+
+        // A glyph only in a non-default layer of the new UFO is skipped and
+        // reported.
+        let mut new_font = norad::Font::load("testdata/MutatorSansLightCondensed.ufo").unwrap();
+        new_font.default_layer_mut().remove_glyph("A");
+        let report = fontgarden
+            .add_source(&new_font, &new_source_name, Location::new())
+            .unwrap();
+        assert_eq!(report.skipped_glyphs, vec![name!("A")]);
+        assert_eq!(
+            vec![
+                ("Latin", "LightCondensed", "foreground", vec!["B"]),
+                ("Latin", "LightCondensed", "support.crossbar", vec!["B"]),
+                ("Latin", "LightWide", "foreground", vec!["A", "B"]),
+            ],
+            glyphs_of_fontgarden(&fontgarden),
+        );
+    }
 
-        let latin_glyphs: HashSet<Name> = collect_names!["A", "Aacute", "S"];
-        let default_glyphs: HashSet<Name> = all_glyphs.difference(&latin_glyphs).cloned().collect();
-        let sets = vec![
-            (name!("Latin"), latin_glyphs),
-            (name!("default"), default_glyphs),
-        ];
+    #[test]
+    fn group_kerning() {
+        use pretty_assertions::assert_eq;
 
-        let mut source_names = HashSet::new();
-        for font in &fonts {
-            let source_name = crate::util::guess_source_name(font).unwrap();
-            source_names.insert(source_name.clone());
+        let mut fontgarden = Fontgarden::new();
 
-            for (set_name, set_glyphs) in &sets {
-                fontgarden
-                    .import(font, set_glyphs, set_name, &source_name)
-                    .unwrap();
-            }
+        let mut font = norad::Font::load("testdata/MutatorSansLightCondensed.ufo").unwrap();
+        font.groups.insert(
+            name!("public.kern1.@MMK_L_A"),
+            vec![name!("A"), name!("Aacute")],
+        );
+        // Groups without the kern1 prefix count as well.
+        font.groups
+            .insert(name!("A_left"), vec![name!("A"), name!("Aacute")]);
+        font.kerning
+            .insert(name!("A_left"), BTreeMap::from([(name!("V"), -20.0)]));
+        let source_name = name!("LightCondensed");
+        fontgarden
+            .import_with_options(
+                &font,
+                &collect_names!["A", "V"],
+                &name!("default"),
+                &source_name,
+                &ImportOptions::default(),
+            )
+            .unwrap();
+        fontgarden
+            .import_with_options(
+                &font,
+                &collect_names!["Aacute"],
+                &name!("Latin"),
+                &source_name,
+                &ImportOptions::default(),
+            )
+            .unwrap();
+
+        // The groups have members in both sets, but their pairs are stored
+        // once.
+        let group_kerning = |fontgarden: &Fontgarden, set_name: &str, group_name: &str| {
+            fontgarden.sets[set_name].sources[&source_name]
+                .kerning
+                .get(group_name)
+                .cloned()
+        };
+        for group_name in ["public.kern1.@MMK_L_A", "A_left"] {
+            assert_eq!(
+                group_kerning(&fontgarden, "Latin", group_name).as_ref(),
+                font.kerning.get(group_name)
+            );
+            assert_eq!(group_kerning(&fontgarden, "default", group_name), None);
         }
 
-        let roundtripped_ufos = fontgarden.export(&all_glyphs, &source_names).unwrap();
+        // Renaming a set can make another set the first one with members.
+        fontgarden
+            .rename_set(&name!("Latin"), &name!("latin"))
+            .unwrap();
+        for group_name in ["public.kern1.@MMK_L_A", "A_left"] {
+            assert_eq!(group_kerning(&fontgarden, "latin", group_name), None);
+            assert_eq!(
+                group_kerning(&fontgarden, "default", group_name).as_ref(),
+                font.kerning.get(group_name)
+            );
+        }
+        fontgarden
+            .rename_set(&name!("latin"), &name!("Latin"))
+            .unwrap();
+        for group_name in ["public.kern1.@MMK_L_A", "A_left"] {
+            assert_eq!(
+                group_kerning(&fontgarden, "Latin", group_name).as_ref(),
+                font.kerning.get(group_name)
+            );
+            assert_eq!(group_kerning(&fontgarden, "default", group_name), None);
+        }
 
-        assert_font_eq(&fonts[0], &roundtripped_ufos["LightWide"]);
-        assert_font_eq(&fonts[1], &roundtripped_ufos["LightCondensed"]);
+        // Once the groups have no members left in a set, their pairs move on.
+        fontgarden
+            .remove_glyphs(&collect_names!["Aacute"], false)
+            .unwrap();
+        for group_name in ["public.kern1.@MMK_L_A", "A_left"] {
+            assert_eq!(group_kerning(&fontgarden, "Latin", group_name), None);
+            assert_eq!(
+                group_kerning(&fontgarden, "default", group_name).as_ref(),
+                font.kerning.get(group_name)
+            );
+        }
+
+        let exports = fontgarden
+            .export(&collect_names!["A", "V"], &collect_names!["LightCondensed"])
+            .unwrap();
+        assert_eq!(
+            exports["LightCondensed"]
+                .kerning
+                .get("public.kern1.@MMK_L_A"),
+            font.kerning.get("public.kern1.@MMK_L_A")
+        );
     }
 
     #[test]
-    fn update_sets() {
-        let mut fontgarden = Fontgarden::new();
-
-        let (mut ufo1, mut ufo2) = (
-            norad::Font::load("testdata/MutatorSansLightWide.ufo").unwrap(),
-            norad::Font::load("testdata/MutatorSansLightCondensed.ufo").unwrap(),
+    fn unkept_designspace_elements() {
+        let tempdir = tempfile::tempdir().unwrap();
+        let path = tempdir.path().join("MyFont.designspace");
+        std::fs::write(
+            &path,
+            "<designspace format=\"4.1\">\n  <rules>\n    <rule name=\"dollar\"/>\n  </rules>\n  <lib>\n    <dict/>\n  </lib>\n</designspace>\n",
+        )
+        .unwrap();
+        assert_eq!(
+            crate::util::unkept_designspace_elements(&path).unwrap(),
+            vec!["rules", "lib"]
         );
 
-        let sets = vec![
-            (name!("Latin"), collect_names!["A"]),
-            (name!("default"), collect_names!["arrowleft"]),
-        ];
+        std::fs::write(&path, "<designspace format=\"4.1\"/>\n").unwrap();
+        assert!(crate::util::unkept_designspace_elements(&path)
+            .unwrap()
+            .is_empty());
+    }
 
-        for font in [&ufo1, &ufo2] {
-            let source_name = crate::util::guess_source_name(font).unwrap();
+    #[test]
+    fn import_designspace() {
+        use crate::designspace::LayerSource;
 
-            for (set_name, set_glyphs) in &sets {
-                fontgarden
-                    .import(font, set_glyphs, set_name, &source_name)
-                    .unwrap();
-            }
-        }
+        let tempdir = tempfile::tempdir().unwrap();
+        let testdata = Path::new(env!("CARGO_MANIFEST_DIR")).join("testdata");
+        let path = tempdir.path().join("MyFont.designspace");
+        let light_path = testdata.join("MutatorSansLightCondensed.ufo");
+        let bold_path = testdata.join("MutatorSansBoldCondensed.ufo");
+        std::fs::write(
+            &path,
+            format!(
+                r#"<?xml version="1.0" encoding="UTF-8"?>
+<designspace format="4.1">
+  <axes>
+    <axis tag="wght" name="weight" minimum="0" maximum="1000" default="0"/>
+  </axes>
+  <sources>
+    <source filename="{light}" name="Light">
+      <location><dimension name="weight" xvalue="0"/></location>
+    </source>
+    <source filename="{bold}" name="MutatorSansBold">
+      <location><dimension name="weight" xvalue="1000"/></location>
+    </source>
+    <source filename="{light}" name="Light.support" layer="support">
+      <location><dimension name="weight" xvalue="500"/></location>
+    </source>
+  </sources>
+</designspace>
+"#,
+                light = light_path.display(),
+                bold = bold_path.display(),
+            ),
+        )
+        .unwrap();
 
-        // Assert the imported glyphs are the same as the UFO ones.
-        assert_glyph_eq(
-            ufo1.get_glyph("A").unwrap(),
-            &fontgarden.sets["Latin"].sources["LightWide"].layers["foreground"].export_glyph("A"),
-        );
-        assert_glyph_eq(
-            ufo2.get_glyph("arrowleft").unwrap(),
-            &fontgarden.sets["default"].sources["LightCondensed"].layers["foreground"]
-                .export_glyph("arrowleft"),
+        let document = norad::designspace::DesignSpaceDocument::load(&path).unwrap();
+        let ufos = crate::util::designspace_ufos(&document, tempdir.path()).unwrap();
+        // The layer source lives in a master's UFO, so it has no entry.
+        assert_eq!(
+            ufos,
+            vec![
+                (light_path, name!("Light")),
+                (bold_path, name!("MutatorSansBold"))
+            ]
         );
 
-        // Modify the glyphs in the UFOs.
-        ufo1.get_glyph_mut("A")
-            .unwrap()
-            .lib
-            .insert("public.markColor".into(), "1,1,1,1".into());
-        ufo2.get_glyph_mut("arrowleft")
-            .unwrap()
-            .lib
-            .insert("bbbb".into(), 1.into());
-
-        // Reimporting the glyphs into any one set should update them in the set
-        // they are in already.
-        let both_glyphs = collect_names!["A", "arrowleft"];
-        for font in [&ufo1, &ufo2] {
-            let source_name = crate::util::guess_source_name(font).unwrap();
+        // Import as the import command does, with the second source renamed.
+        let mut fontgarden = Fontgarden::new();
+        let renames = HashMap::from([(name!("MutatorSansBold"), name!("Bold"))]);
+        for (ufo_path, source_name) in &ufos {
+            let source_name = renames.get(source_name).unwrap_or(source_name);
+            let font = norad::Font::load(ufo_path).unwrap();
             fontgarden
-                .import(font, &both_glyphs, &sets[0].0, &source_name)
+                .import_with_options(
+                    &font,
+                    &collect_names!["A"],
+                    &name!("Latin"),
+                    source_name,
+                    &ImportOptions::default(),
+                )
                 .unwrap();
         }
+        fontgarden.import_designspace(&document, &renames).unwrap();
 
-        assert_glyph_eq(
-            ufo1.get_glyph("A").unwrap(),
-            &fontgarden.sets["Latin"].sources["LightWide"].layers["foreground"].export_glyph("A"),
+        assert_eq!(
+            fontgarden.sources.keys().collect::<Vec<_>>(),
+            vec!["Bold", "Light"]
         );
-        assert_glyph_eq(
-            ufo2.get_glyph("arrowleft").unwrap(),
-            &fontgarden.sets["default"].sources["LightCondensed"].layers["foreground"]
-                .export_glyph("arrowleft"),
+        let light = &fontgarden.sources["Light"];
+        assert_eq!(light.filename, "MutatorSansLightCondensed");
+        assert_eq!(light.location, Location::from([("weight".into(), 0.0)]));
+        let bold = &fontgarden.sources["Bold"];
+        assert_eq!(bold.filename, "MutatorSansBoldCondensed");
+        assert_eq!(bold.location, Location::from([("weight".into(), 1000.0)]));
+
+        assert_eq!(
+            fontgarden
+                .designspace
+                .axes
+                .iter()
+                .map(|axis| axis.name.as_str())
+                .collect::<Vec<_>>(),
+            vec!["weight"]
+        );
+        assert_eq!(
+            fontgarden.designspace.layer_sources,
+            BTreeMap::from([(
+                name!("Light.support"),
+                LayerSource {
+                    source: name!("Light"),
+                    layer: name!("support"),
+                    location: Location::from([("weight".into(), 500.0)]),
+                }
+            )])
         );
     }
 
     #[test]
-    fn follow_components() {
+    fn designspace_save_load_export() {
+        use crate::designspace::{Axis, Instance, LayerSource};
         use pretty_assertions::assert_eq;
 
         let mut fontgarden = Fontgarden::new();
-
-        let fonts = [
-            norad::Font::load("testdata/MutatorSansLightWide.ufo").unwrap(),
-            norad::Font::load("testdata/MutatorSansLightCondensed.ufo").unwrap(),
+        fontgarden.designspace.axes = vec![
+            Axis {
+                name: "width".into(),
+                tag: "wdth".into(),
+                minimum: Some(0.0),
+                default: 0.0,
+                maximum: Some(1000.0),
+                hidden: false,
+                map: vec![],
+            },
+            Axis {
+                name: "weight".into(),
+                tag: "wght".into(),
+                minimum: Some(100.0),
+                default: 100.0,
+                maximum: Some(900.0),
+                hidden: false,
+                map: vec![(100.0, 0.0), (900.0, 1000.0)],
+            },
         ];
-
-        let sets = vec![
-            (name!("Latin"), collect_names!["Aacute"]),
-            (name!("Punctuation"), collect_names!["semicolon"]),
+        fontgarden.sources = BTreeMap::from([
+            (
+                name!("LightCondensed"),
+                SourceRecord {
+                    filename: "MutatorSansLightCondensed".into(),
+                    location: BTreeMap::from([("width".into(), 0.0), ("weight".into(), 0.0)]),
+                    ..SourceRecord::new(&name!("LightCondensed"))
+                },
+            ),
+            (
+                name!("BoldWide"),
+                SourceRecord {
+                    location: BTreeMap::from([("width".into(), 1000.0), ("weight".into(), 1000.0)]),
+                    ..SourceRecord::new(&name!("BoldWide"))
+                },
+            ),
+        ]);
+        fontgarden.designspace.layer_sources = BTreeMap::from([(
+            name!("support"),
+            LayerSource {
+                source: name!("LightCondensed"),
+                layer: name!("support"),
+                location: BTreeMap::from([("width".into(), 0.0), ("weight".into(), 500.5)]),
+            },
+        )]);
+        fontgarden.designspace.instances = vec![
+            Instance {
+                familyname: Some("MutatorMathTest".into()),
+                stylename: Some("Medium".into()),
+                filename: Some("instances/MutatorMathTest-Medium.ufo".into()),
+                location: BTreeMap::from([("weight".into(), 500.0)]),
+                ..Default::default()
+            },
+            Instance {
+                familyname: Some("MutatorMathTest".into()),
+                stylename: Some("BoldWide".into()),
+                filename: Some("instances/MutatorMathTest-BoldWide.ufo".into()),
+                location: BTreeMap::from([("width".into(), 1000.0), ("weight".into(), 1000.0)]),
+                ..Default::default()
+            },
         ];
-        let all_glyphs: HashSet<Name> = collect_names!["Aacute", "semicolon"];
 
-        for font in &fonts {
-            let source_name = crate::util::guess_source_name(font).unwrap();
-            for (set_name, set_glyphs) in &sets {
-                fontgarden
-                    .import(font, set_glyphs, set_name, &source_name)
-                    .unwrap();
-            }
-        }
+        let tempdir = tempfile::tempdir().unwrap();
+        fontgarden.save(tempdir.path()).unwrap();
+        let fontgarden2 = Fontgarden::from_path_with_report(tempdir.path()).unwrap().0;
+        assert_eq!(fontgarden, fontgarden2);
 
+        let document = fontgarden.designspace.to_designspace_document(
+            &fontgarden.sources,
+            &collect_names!["LightCondensed"],
+            &fontgarden.font_info,
+        );
+        assert_eq!(document.axes.len(), 2);
+        // The bold wide instance needs the BoldWide source.
+        let stylenames = |document: &norad::designspace::DesignSpaceDocument| {
+            document
+                .instances
+                .iter()
+                .map(|i| i.stylename.clone().unwrap())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(stylenames(&document), vec!["Medium"]);
         assert_eq!(
+            document
+                .sources
+                .iter()
+                .map(|s| (s.filename.as_str(), s.layer.as_deref()))
+                .collect::<Vec<_>>(),
             vec![
-                (
-                    "Latin",
-                    "LightCondensed",
-                    "foreground",
-                    vec!["A", "Aacute", "acute"],
-                ),
-                ("Latin", "LightCondensed", "support", vec!["A"]),
-                (
-                    "Latin",
-                    "LightWide",
-                    "foreground",
-                    vec!["A", "Aacute", "acute"],
-                ),
-                (
-                    "Punctuation",
-                    "LightCondensed",
-                    "foreground",
-                    vec!["comma", "period", "semicolon"],
-                ),
-                (
-                    "Punctuation",
-                    "LightWide",
-                    "foreground",
-                    vec!["comma", "period", "semicolon"],
-                ),
-            ],
-            glyphs_of_fontgarden(&fontgarden),
+                ("MutatorSansLightCondensed.ufo", None),
+                ("MutatorSansLightCondensed.ufo", Some("support"))
+            ]
         );
 
-        let source_names = collect_names!["LightWide", "LightCondensed"];
-        let exports = fontgarden.export(&all_glyphs, &source_names).unwrap();
+        let document = fontgarden.designspace.to_designspace_document(
+            &fontgarden.sources,
+            &collect_names!["LightCondensed", "BoldWide"],
+            &fontgarden.font_info,
+        );
+        assert_eq!(stylenames(&document), vec!["Medium", "BoldWide"]);
+    }
 
-        assert_eq!(
-            vec![
-                (
-                    "LightCondensed",
-                    "foreground",
-                    vec!["A", "Aacute", "acute", "comma", "period", "semicolon"]
-                ),
-                ("LightCondensed", "support", vec!["A"]),
-                (
-                    "LightWide",
-                    "foreground",
-                    vec!["A", "Aacute", "acute", "comma", "period", "semicolon"]
+    #[test]
+    fn import_two_designspaces() {
+        use crate::errors::LoadDesignspaceError;
+
+        let tempdir = tempfile::tempdir().unwrap();
+        // A designspace with a weight axis up to `maximum`, masters at its
+        // ends and the given extra sources and a Medium instance.
+        let load_designspace =
+            |name: &str,
+             maximum: u32,
+             (light, bold): (&str, &str),
+             extra_sources: &str,
+             (medium, medium_weight): (&str, u32)| {
+                let path = tempdir.path().join(format!("{name}.designspace"));
+                std::fs::write(
+                    &path,
+                    format!(
+                        r#"<?xml version="1.0" encoding="UTF-8"?>
+<designspace format="4.1">
+  <axes>
+    <axis tag="wght" name="weight" minimum="0" maximum="{maximum}" default="0"/>
+  </axes>
+  <sources>
+    <source filename="MutatorSans{light}.ufo" name="{light}">
+      <location><dimension name="weight" xvalue="0"/></location>
+    </source>
+    <source filename="MutatorSans{bold}.ufo" name="{bold}">
+      <location><dimension name="weight" xvalue="{maximum}"/></location>
+    </source>
+    {extra_sources}
+  </sources>
+  <instances>
+    <instance familyname="MutatorMathTest" stylename="{medium}">
+      <location><dimension name="weight" xvalue="{medium_weight}"/></location>
+    </instance>
+  </instances>
+</designspace>
+"#
+                    ),
                 )
-            ],
-            glyphs_of_exported_fonts(&exports),
+                .unwrap();
+                norad::designspace::DesignSpaceDocument::load(&path).unwrap()
+            };
+        let support = r#"<source filename="MutatorSansLightCondensed.ufo" name="LightCondensed.support" layer="support">
+      <location><dimension name="weight" xvalue="500"/></location>
+    </source>"#;
+        let upright = load_designspace(
+            "Upright",
+            1000,
+            ("LightCondensed", "BoldCondensed"),
+            support,
+            ("Medium", 500),
+        );
+        let italic = load_designspace(
+            "Italic",
+            1000,
+            ("LightWide", "BoldWide"),
+            "",
+            ("Medium Italic", 500),
+        );
+
+        let mut fontgarden = Fontgarden::new();
+        let no_renames = HashMap::new();
+        fontgarden
+            .import_designspace(&upright, &no_renames)
+            .unwrap();
+        fontgarden.import_designspace(&italic, &no_renames).unwrap();
+
+        // Both designspaces are kept, the axis once.
+        assert_eq!(
+            fontgarden.sources.keys().collect::<Vec<_>>(),
+            vec!["BoldCondensed", "BoldWide", "LightCondensed", "LightWide"]
+        );
+        assert_eq!(fontgarden.sources["BoldWide"].location["weight"], 1000.0);
+        assert_eq!(fontgarden.designspace.axes.len(), 1);
+        assert_eq!(
+            fontgarden
+                .designspace
+                .layer_sources
+                .keys()
+                .collect::<Vec<_>>(),
+            vec!["LightCondensed.support"]
+        );
+        let instances = |fontgarden: &Fontgarden| {
+            fontgarden
+                .designspace
+                .instances
+                .iter()
+                .map(|i| (i.stylename.clone().unwrap(), i.location["weight"]))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            instances(&fontgarden),
+            vec![("Medium".into(), 500.0), ("Medium Italic".into(), 500.0)]
+        );
+
+        // Importing an updated designspace again replaces its instances.
+        let updated = load_designspace(
+            "Upright",
+            1000,
+            ("LightCondensed", "BoldCondensed"),
+            support,
+            ("Medium", 400),
+        );
+        fontgarden
+            .import_designspace(&updated, &no_renames)
+            .unwrap();
+        assert_eq!(
+            instances(&fontgarden),
+            vec![("Medium".into(), 400.0), ("Medium Italic".into(), 500.0)]
+        );
+
+        // Conflicting documents are rejected without changing anything.
+        let other_axis = load_designspace(
+            "OtherAxis",
+            900,
+            ("LightWide", "BoldWide"),
+            "",
+            ("Semibold", 600),
+        );
+        assert!(matches!(
+            fontgarden.import_designspace(&other_axis, &no_renames),
+            Err(LoadDesignspaceError::AxisMismatch(axis)) if axis == "weight"
+        ));
+        let other_support = r#"<source filename="MutatorSansBoldCondensed.ufo" name="LightCondensed.support" layer="support">
+      <location><dimension name="weight" xvalue="500"/></location>
+    </source>"#;
+        let other_layer_source = load_designspace(
+            "OtherLayerSource",
+            1000,
+            ("LightCondensed", "BoldCondensed"),
+            other_support,
+            ("Semibold", 600),
+        );
+        assert!(matches!(
+            fontgarden.import_designspace(&other_layer_source, &no_renames),
+            Err(LoadDesignspaceError::LayerSourceConflict(name, _, source))
+                if name == "LightCondensed.support" && source == "BoldCondensed"
+        ));
+        assert_eq!(fontgarden.designspace.axes[0].maximum, Some(1000.0));
+        assert_eq!(fontgarden.sources["BoldWide"].location["weight"], 1000.0);
+        assert_eq!(
+            instances(&fontgarden),
+            vec![("Medium".into(), 400.0), ("Medium Italic".into(), 500.0)]
+        );
+    }
+
+    #[test]
+    fn source_registry() {
+        use pretty_assertions::assert_eq;
+
+        let mut fontgarden = Fontgarden::new();
+
+        let font = norad::Font::load("testdata/MutatorSansLightCondensed.ufo").unwrap();
+        fontgarden
+            .import_with_options(
+                &font,
+                &collect_names!["A"],
+                &name!("Latin"),
+                &name!("LightCondensed"),
+                &ImportOptions::default(),
+            )
+            .unwrap();
+        fontgarden
+            .sources
+            .insert(name!("Empty"), SourceRecord::new(&name!("Empty")));
+
+        assert_eq!(
+            fontgarden.sources["LightCondensed"],
+            SourceRecord {
+                italic: false,
+                default_layer: name!("foreground"),
+                layer_order: vec![
+                    name!("support"),
+                    name!("support.crossbar"),
+                    name!("background"),
+                    name!("support.S.wide"),
+                    name!("support.S.middle"),
+                ],
+                ..SourceRecord::new(&name!("LightCondensed"))
+            }
         );
+
+        // Sources without glyphs in any set survive a roundtrip.
+        let tempdir = tempfile::tempdir().unwrap();
+        fontgarden.save(tempdir.path()).unwrap();
+        let fontgarden2 = Fontgarden::from_path_with_report(tempdir.path()).unwrap().0;
+        assert_eq!(fontgarden, fontgarden2);
+
+        // Source directories must be registered.
+        fontgarden.sources.remove("LightCondensed");
+        fontgarden.save(tempdir.path()).unwrap();
+        assert!(matches!(
+            Fontgarden::from_path_with_report(tempdir.path()),
+            Err(LoadError::UnknownSource(set, source)) if set == name!("Latin") && source == name!("LightCondensed")
+        ));
+    }
+
+    /// Save a copy of the LightCondensed test font into `dir` in which B
+    /// references an image that fails to load, and load it.
+    fn font_with_broken_image(dir: &Path) -> norad::Font {
+        let ufo_path = dir.join("Font.ufo");
+        norad::Font::load("testdata/MutatorSansLightCondensed.ufo")
+            .unwrap()
+            .save(&ufo_path)
+            .unwrap();
+        let glif_path = ufo_path.join("glyphs/B_.glif");
+        let glif = std::fs::read_to_string(&glif_path).unwrap();
+        let glif = glif.replacen("<outline>", "<image fileName=\"image\"/>\n  <outline>", 1);
+        std::fs::write(&glif_path, glif).unwrap();
+        std::fs::write(ufo_path.join("images/image"), b"not a PNG").unwrap();
+        norad::Font::load(&ufo_path).unwrap()
     }
 
     /// Roundtrip UFO colors to make equality testing easier, because we
@@ -1002,6 +6513,9 @@ mod tests {
         let layer_names: Vec<_> = font.layers.iter().map(|l| l.name()).cloned().collect();
         for layer_name in layer_names {
             let layer = font.layers.get_mut(&layer_name).unwrap();
+            if let Some(color) = layer.color.take() {
+                layer.color = Some(Color::from_str(&color.to_rgba_string()).unwrap());
+            }
             for glyph in layer.iter_mut() {
                 if let Some(color_string) = glyph.lib.remove("public.markColor") {
                     let our_color = Color::from_str(color_string.as_string().unwrap()).unwrap();
@@ -1055,13 +6569,27 @@ mod tests {
         // NOTE: goes over individual fields and glyphs to have finer grained
         // and faster diffs. Big diffs == slow.
         //
-        // TODO: compare more than glyphs.
+        // TODO: compare more than glyphs, layers, font info, groups and
+        // kerning.
+        assert_eq!(reference.font_info, other.font_info);
+        assert_eq!(reference.groups, other.groups);
+        assert_eq!(reference.kerning, other.kerning);
+        assert_eq!(
+            reference
+                .layers
+                .iter()
+                .map(|l| l.name())
+                .collect::<Vec<_>>(),
+            other.layers.iter().map(|l| l.name()).collect::<Vec<_>>()
+        );
         for reference_layer in reference.layers.iter() {
             let other_layer = other.layers.get(reference_layer.name()).unwrap();
+            assert_eq!(reference_layer.color, other_layer.color);
+            assert_eq!(reference_layer.lib, other_layer.lib);
 
             assert_eq!(reference_layer.len(), other_layer.len());
             for reference in reference_layer.iter() {
-                let other = other_layer.get_glyph(&reference.name()).unwrap();
+                let other = other_layer.get_glyph(reference.name()).unwrap();
                 assert_glyph_eq(reference, other);
             }
         }
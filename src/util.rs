@@ -1,11 +1,29 @@
 use std::{
-    collections::{BTreeMap, HashSet},
+    collections::{BTreeMap, HashMap, HashSet},
     path::{Path, PathBuf},
 };
 
 use norad::Name;
+use serde::Deserialize;
+use unicode_script::Script;
 
-use crate::structs::GlyphRecord;
+use crate::{
+    errors::{ImportError, LoadDesignspaceError, LoadRenameMappingError, LoadSourceMappingError},
+    structs::GlyphRecord,
+};
+
+/// A row of a source mapping file.
+///
+/// Either `ufo` is given, which matches a UFO by path (relative to the mapping
+/// file), or `family_name` and `style_name`, which match a UFO by its font
+/// info. Empty family or style names match UFOs without them.
+#[derive(Debug, Deserialize)]
+pub(crate) struct SourceMappingRecord {
+    pub ufo: Option<PathBuf>,
+    pub family_name: Option<String>,
+    pub style_name: Option<String>,
+    pub source_name: Name,
+}
 
 // TODO: Refactor to return errors
 pub(crate) fn extract_glyph_data(
@@ -31,7 +49,6 @@ pub(crate) fn extract_glyph_data(
             .collect(),
         None => HashSet::new(),
     };
-
     for name in glyphs {
         let mut record = GlyphRecord {
             codepoints: font
@@ -49,17 +66,91 @@ pub(crate) fn extract_glyph_data(
                 None => crate::structs::OpenTypeCategory::Unassigned,
             };
         }
-        if skip_exports.contains(name.as_ref()) {
-            record.export = false;
-        } else {
-            record.export = true;
-        }
+        record.export = !skip_exports.contains(name.as_ref());
         glyph_data.insert(name.clone(), record);
     }
 
     glyph_data
 }
 
+/// Read the `public.glyphOrder` of the UFO.
+pub(crate) fn ufo_glyph_order(font: &norad::Font) -> Result<Vec<Name>, ImportError> {
+    match font.lib.get("public.glyphOrder") {
+        Some(v) => v
+            .as_array()
+            .ok_or(ImportError::InvalidGlyphOrder)?
+            .iter()
+            .map(|v| {
+                v.as_string()
+                    .and_then(|name| Name::new(name).ok())
+                    .ok_or(ImportError::InvalidGlyphOrder)
+            })
+            .collect(),
+        None => Ok(Vec::new()),
+    }
+}
+
+/// Merge the glyph order of an imported UFO into the stored glyph order.
+///
+/// Imported glyphs listed in the UFO order are placed after the nearest stored
+/// glyph that comes before them in the UFO order, or at the front if there is
+/// none. Other stored glyphs keep their position.
+pub(crate) fn merge_glyph_order(
+    stored: &[Name],
+    ufo_order: &[Name],
+    imported: &HashSet<Name>,
+) -> Vec<Name> {
+    let mut ufo_positions: HashMap<&Name, usize> = HashMap::new();
+    for (position, name) in ufo_order.iter().enumerate() {
+        ufo_positions.entry(name).or_insert(position);
+    }
+
+    let kept: Vec<&Name> = stored
+        .iter()
+        .filter(|name| !(imported.contains(*name) && ufo_positions.contains_key(name)))
+        .collect();
+    let mut anchors: Vec<(usize, usize)> = kept
+        .iter()
+        .enumerate()
+        .filter_map(|(index, name)| ufo_positions.get(name).map(|&position| (position, index)))
+        .collect();
+    anchors.sort();
+
+    // Imported glyphs to place at the front and after each kept glyph.
+    let mut front = Vec::new();
+    let mut after: Vec<Vec<&Name>> = vec![Vec::new(); kept.len()];
+    let mut seen = HashSet::new();
+    for (position, name) in ufo_order.iter().enumerate() {
+        if !imported.contains(name) || !seen.insert(name) {
+            continue;
+        }
+        match anchors.partition_point(|&(anchor, _)| anchor < position) {
+            0 => front.push(name),
+            n => after[anchors[n - 1].1].push(name),
+        }
+    }
+
+    let mut merged: Vec<Name> = front.into_iter().cloned().collect();
+    for (name, placed) in kept.into_iter().zip(after) {
+        merged.push(name.clone());
+        merged.extend(placed.into_iter().cloned());
+    }
+    merged
+}
+
+/// `file_name` with `number` inserted before the extension, e.g.
+/// "image.2.png" for "image.png".
+pub(crate) fn numbered_file_name(file_name: &Path, number: usize) -> PathBuf {
+    let stem = file_name.file_stem().unwrap_or_default().to_string_lossy();
+    match file_name.extension() {
+        Some(extension) => {
+            let extension = extension.to_string_lossy();
+            file_name.with_file_name(format!("{stem}.{number}.{extension}"))
+        }
+        None => file_name.with_file_name(format!("{stem}.{number}")),
+    }
+}
+
 // TODO: Refactor to return errors
 pub(crate) fn load_glyph_list(path: &Path) -> Result<HashSet<Name>, std::io::Error> {
     let names: HashSet<Name> = std::fs::read_to_string(path)?
@@ -99,16 +190,462 @@ pub(crate) fn glyphset_follow_composites(
     discovered_glyphs
 }
 
+/// Load a source mapping CSV file with the columns `ufo`, `family_name`,
+/// `style_name` and `source_name`. Each UFO, family and style name pair and
+/// source name may only be listed once.
+pub(crate) fn load_source_mapping(
+    path: &Path,
+) -> Result<Vec<SourceMappingRecord>, LoadSourceMappingError> {
+    let base_dir = path.parent().unwrap_or_else(|| Path::new(""));
+
+    let mut records: Vec<SourceMappingRecord> = Vec::new();
+    let mut reader = csv::Reader::from_path(path)?;
+    for result in reader.deserialize() {
+        let mut record: SourceMappingRecord = result?;
+        record.ufo = record.ufo.map(|ufo| base_dir.join(ufo));
+        for other in records.iter() {
+            if other.source_name == record.source_name {
+                return Err(LoadSourceMappingError::DuplicateSourceName(
+                    record.source_name,
+                ));
+            }
+            let same_entry = match (&record.ufo, &other.ufo) {
+                (Some(ufo), Some(other_ufo)) => ufo == other_ufo,
+                (None, None) => {
+                    record.family_name == other.family_name && record.style_name == other.style_name
+                }
+                _ => false,
+            };
+            if same_entry {
+                let entry = match &record.ufo {
+                    Some(ufo) => ufo.display().to_string(),
+                    None => format!(
+                        "{} {}",
+                        record.family_name.as_deref().unwrap_or_default(),
+                        record.style_name.as_deref().unwrap_or_default()
+                    ),
+                };
+                return Err(LoadSourceMappingError::DuplicateEntry(entry));
+            }
+        }
+        records.push(record);
+    }
+
+    Ok(records)
+}
+
+/// Load a glyph rename CSV file with the columns `old` and `new`. Each old
+/// name may only be listed once.
+pub(crate) fn load_rename_mapping(
+    path: &Path,
+) -> Result<BTreeMap<Name, Name>, LoadRenameMappingError> {
+    let mut reader = csv::Reader::from_path(path)?;
+    let headers = reader.headers()?;
+    if headers.iter().ne(["old", "new"]) {
+        return Err(LoadRenameMappingError::InvalidHeaders(
+            headers.iter().collect::<Vec<_>>().join(","),
+        ));
+    }
+
+    let mut renames = BTreeMap::new();
+    for result in reader.deserialize() {
+        let (old, new): (Name, Name) = result?;
+        if renames.contains_key(&old) {
+            return Err(LoadRenameMappingError::DuplicateOldName(old));
+        }
+        renames.insert(old, new);
+    }
+
+    Ok(renames)
+}
+
+/// Keywords of feature code. Glyph names that clash with them must be
+/// escaped with a backslash, so these are never glyph names.
+const FEATURE_KEYWORDS: &[&str] = &[
+    "anchor",
+    "anchorDef",
+    "anon",
+    "anonymous",
+    "Ascender",
+    "Attach",
+    "base",
+    "by",
+    "CaretOffset",
+    "CapHeight",
+    "Character",
+    "CodePageRange",
+    "contour",
+    "contourpoint",
+    "cursive",
+    "cvParameters",
+    "Descender",
+    "device",
+    "enum",
+    "enumerate",
+    "excludeDFLT",
+    "exclude_dflt",
+    "FamilyClass",
+    "feature",
+    "featureNames",
+    "FeatUILabelNameID",
+    "FeatUITooltipTextNameID",
+    "FontRevision",
+    "from",
+    "FSType",
+    "GlyphClassDef",
+    "ignore",
+    "IgnoreBaseGlyphs",
+    "IgnoreLigatures",
+    "IgnoreMarks",
+    "include",
+    "includeDFLT",
+    "include_dflt",
+    "language",
+    "languagesystem",
+    "LigatureCaretByDev",
+    "LigatureCaretByIndex",
+    "LigatureCaretByPos",
+    "ligature",
+    "ligComponent",
+    "LineGap",
+    "lookup",
+    "lookupflag",
+    "LowerOpSize",
+    "mark",
+    "MarkAttachClass",
+    "MarkAttachmentType",
+    "markClass",
+    "name",
+    "nameid",
+    "NULL",
+    "Panose",
+    "ParamUILabelNameID",
+    "parameters",
+    "pos",
+    "position",
+    "required",
+    "reversesub",
+    "RightToLeft",
+    "rsub",
+    "SampleTextNameID",
+    "script",
+    "sizemenuname",
+    "sub",
+    "substitute",
+    "subtable",
+    "table",
+    "TypoAscender",
+    "TypoDescender",
+    "TypoLineGap",
+    "UnicodeRange",
+    "UpperOpSize",
+    "useExtension",
+    "UseMarkFilteringSet",
+    "valueRecordDef",
+    "Vendor",
+    "VertAdvanceY",
+    "VertOriginY",
+    "VertTypoAscender",
+    "VertTypoDescender",
+    "VertTypoLineGap",
+    "WeightClass",
+    "WidthClass",
+    "winAscent",
+    "winDescent",
+    "XHeight",
+];
+
+/// Keywords followed by a tag or label, e.g. `liga` in `feature liga {`.
+const FEATURE_LABEL_KEYWORDS: &[&str] = &["feature", "lookup", "table"];
+
+/// Keywords of statements without glyph names, e.g. `language DEU;` or
+/// `languagesystem DFLT dflt;`.
+const FEATURE_TAG_STATEMENT_KEYWORDS: &[&str] = &[
+    "anchorDef",
+    "include",
+    "language",
+    "languagesystem",
+    "name",
+    "nameid",
+    "script",
+    "sizemenuname",
+    "valueRecordDef",
+];
+
+/// Characters that may appear in a glyph name in feature code.
+fn is_feature_name_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || "._-*+:^|~".contains(c)
+}
+
+/// The glyph names in feature code with their byte offsets.
+///
+/// Comments, strings, class names, keywords, tags, labels, numbers and the
+/// contents of angle brackets are skipped. Names escaped with a backslash are
+/// returned without it. In glyph classes, hyphenated names that `is_glyph`
+/// does not know are taken as ranges like `a-z`, and their ends are returned
+/// instead.
+pub(crate) fn feature_glyph_names(
+    features: &str,
+    is_glyph: impl Fn(&str) -> bool,
+) -> Vec<(usize, &str)> {
+    let mut names = Vec::new();
+    let mut offset = 0;
+    // Whether the next name is a tag or label, e.g. after `}` in `} liga;`.
+    let mut label_next = false;
+    let mut in_tag_statement = false;
+    let mut in_class = false;
+    while let Some(c) = features[offset..].chars().next() {
+        let rest = &features[offset..];
+        let end_of_name = |start: usize| {
+            rest[start..]
+                .find(|c| !is_feature_name_char(c))
+                .map_or(rest.len(), |i| start + i)
+        };
+        let len = match c {
+            '#' => rest.find('\n').unwrap_or(rest.len()),
+            '"' => rest[1..].find('"').map_or(rest.len(), |i| i + 2),
+            '<' => rest.find('>').map_or(rest.len(), |i| i + 1),
+            '@' => end_of_name(1),
+            '\\' => {
+                let len = end_of_name(1);
+                let name = &rest[1..len];
+                // NOTE: Escaped numbers are CIDs, not glyph names.
+                if !in_tag_statement && !name.starts_with(|c: char| c.is_ascii_digit()) {
+                    names.push((offset + 1, name));
+                }
+                label_next = false;
+                len
+            }
+            c if is_feature_name_char(c) => {
+                let len = end_of_name(0);
+                let name = &rest[..len];
+                if label_next {
+                    label_next = false;
+                } else if FEATURE_LABEL_KEYWORDS.contains(&name) {
+                    label_next = true;
+                } else if FEATURE_TAG_STATEMENT_KEYWORDS.contains(&name) {
+                    in_tag_statement = true;
+                } else if !in_tag_statement
+                    && !FEATURE_KEYWORDS.contains(&name)
+                    && !name.starts_with(|c: char| c.is_ascii_digit() || c == '-')
+                {
+                    let range = if in_class && !is_glyph(name) {
+                        range_hyphen(name, &is_glyph)
+                    } else {
+                        None
+                    };
+                    match range {
+                        Some(i) => {
+                            names.push((offset, &name[..i]));
+                            names.push((offset + i + 1, &name[i + 1..]));
+                        }
+                        None => names.push((offset, name)),
+                    }
+                }
+                len
+            }
+            c => {
+                match c {
+                    ';' => in_tag_statement = false,
+                    '[' => in_class = true,
+                    ']' => in_class = false,
+                    _ => (),
+                }
+                if !c.is_whitespace() {
+                    label_next = c == '}';
+                }
+                c.len_utf8()
+            }
+        };
+        offset += len;
+    }
+    names
+}
+
+/// The position of the hyphen that splits `name` into the ends of a glyph
+/// range: the first one with glyphs on both sides, else the only one.
+fn range_hyphen(name: &str, is_glyph: impl Fn(&str) -> bool) -> Option<usize> {
+    let hyphens: Vec<usize> = name.match_indices('-').map(|(i, _)| i).collect();
+    hyphens
+        .iter()
+        .copied()
+        .find(|&i| is_glyph(&name[..i]) && is_glyph(&name[i + 1..]))
+        .or(match hyphens[..] {
+            [i] => Some(i),
+            _ => None,
+        })
+}
+
+/// Rewrite the glyph names in `renames` in feature code. Only whole names
+/// are rewritten, see [`feature_glyph_names`] for what `is_glyph` is for.
+pub(crate) fn rename_glyphs_in_features(
+    features: &str,
+    renames: &BTreeMap<Name, Name>,
+    is_glyph: impl Fn(&str) -> bool,
+) -> String {
+    let mut renamed = String::with_capacity(features.len());
+    let mut last = 0;
+    for (offset, name) in feature_glyph_names(features, is_glyph) {
+        if let Some(new) = renames.get(name) {
+            renamed.push_str(&features[last..offset]);
+            renamed.push_str(new);
+            last = offset + name.len();
+        }
+    }
+    renamed.push_str(&features[last..]);
+    renamed
+}
+
+/// The Unicode script named `name`, either by its full name like "Cyrillic"
+/// or "Old_Italic", or by its four-letter short name like "Cyrl". Case is
+/// ignored, and so are spaces instead of underscores.
+pub(crate) fn parse_script(name: &str) -> Option<Script> {
+    let title_case: Vec<String> = name
+        .split(['_', ' '])
+        .map(|word| {
+            let mut chars = word.chars();
+            match chars.next() {
+                Some(first) => first
+                    .to_uppercase()
+                    .chain(chars.flat_map(char::to_lowercase))
+                    .collect(),
+                None => String::new(),
+            }
+        })
+        .collect();
+    let title_case = title_case.join("_");
+    Script::from_full_name(name)
+        .or_else(|| Script::from_full_name(&title_case))
+        .or_else(|| Script::from_short_name(&title_case))
+}
+
+/// Look up the source name of the UFO at `path` in a source mapping. Matches
+/// by path take precedence over matches by family and style name.
+pub(crate) fn map_source_name(
+    mapping: &[SourceMappingRecord],
+    path: &Path,
+    font: &norad::Font,
+) -> Option<Name> {
+    let same_path = |other: &Path| match (path.canonicalize(), other.canonicalize()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => path == other,
+    };
+
+    mapping
+        .iter()
+        .find(|record| record.ufo.as_deref().is_some_and(same_path))
+        .or_else(|| {
+            mapping.iter().find(|record| {
+                record.ufo.is_none()
+                    && record.family_name == font.font_info.family_name
+                    && record.style_name == font.font_info.style_name
+            })
+        })
+        .map(|record| record.source_name.clone())
+}
+
+/// The elements of the designspace file at `path` that are not kept in the
+/// design space data, i.e. rules and libs. They are not part of the parsed
+/// document, so look for them in the file itself.
+pub(crate) fn unkept_designspace_elements(path: &Path) -> std::io::Result<Vec<&'static str>> {
+    let contents = std::fs::read_to_string(path)?;
+    let elements = ["rules", "lib"]
+        .into_iter()
+        .filter(|element| {
+            contents.contains(&format!("<{element}>")) || contents.contains(&format!("<{element} "))
+        })
+        .collect();
+    Ok(elements)
+}
+
+/// Resolve the sources of a designspace document to UFO paths (relative to
+/// `base_dir`) and source names.
+///
+/// Source names are the designspace source names, or the UFO file names
+/// without extension if unnamed. Sparse layer sources live in the UFO of a
+/// master source, and all layers of a UFO are imported anyway, so they only
+/// get their own entry if their UFO is not a master itself.
+pub(crate) fn designspace_ufos(
+    designspace: &norad::designspace::DesignSpaceDocument,
+    base_dir: &Path,
+) -> Result<Vec<(PathBuf, Name)>, LoadDesignspaceError> {
+    let (masters, layer_sources): (Vec<_>, Vec<_>) = designspace
+        .sources
+        .iter()
+        .partition(|source| source.layer.is_none());
+
+    let mut ufos: Vec<(PathBuf, Name)> = Vec::new();
+    for source in masters.into_iter().chain(layer_sources) {
+        let ufo_path = base_dir.join(&source.filename);
+        if ufos.iter().any(|(path, _)| *path == ufo_path) {
+            continue;
+        }
+        ufos.push((ufo_path, designspace_source_name(source)?));
+    }
+
+    Ok(ufos)
+}
+
+pub(crate) fn designspace_source_name(
+    source: &norad::designspace::Source,
+) -> Result<Name, LoadDesignspaceError> {
+    let name = if source.name.is_empty() {
+        Path::new(&source.filename)
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default()
+    } else {
+        source.name.clone()
+    };
+    Name::new(&name).map_err(|e| LoadDesignspaceError::NamingError(name.clone(), e))
+}
+
 pub(crate) fn guess_source_name(font: &norad::Font) -> Option<Name> {
     match font.font_info.style_name.as_ref() {
-        Some(string) => match Name::new(string) {
-            Ok(name) => Some(name),
-            Err(_) => None,
-        },
+        Some(string) => Name::new(string).ok(),
         None => None,
     }
 }
 
+/// Hash `data` into a hex string that can be used as a file name, so that
+/// identical files are stored once.
+///
+/// NOTE: 64-bit FNV-1a. Not cryptographic, but stable across platforms and
+/// releases, which `std`'s hashers are not. Different data can have the same
+/// hash, so callers must compare the stored bytes on a hit.
+pub(crate) fn content_hash(data: &[u8]) -> String {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in data {
+        hash ^= u64::from(*byte);
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    format!("{hash:016x}")
+}
+
+/// Add `data` to `store` under its content hash unless it is stored already,
+/// and return its key.
+///
+/// Different data with the same hash goes under the next free key of the
+/// form `<hash>-<n>`, see [`content_hash`].
+pub(crate) fn insert_by_content(store: &mut BTreeMap<String, Vec<u8>>, data: &[u8]) -> String {
+    let hash = content_hash(data);
+    let mut key = hash.clone();
+    let mut n = 1;
+    loop {
+        match store.get(&key) {
+            Some(stored) if stored.as_slice() != data => {
+                n += 1;
+                key = format!("{hash}-{n}");
+            }
+            Some(_) => return key,
+            None => {
+                store.insert(key.clone(), data.to_vec());
+                return key;
+            }
+        }
+    }
+}
+
 /// Given a glyph `name`, return an appropriate file name.
 ///
 /// NOTE: Copied from norad 0.7
@@ -278,3 +815,269 @@ fn user_name_to_file_name(
 
     result.into()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn renames(pairs: &[(&str, &str)]) -> BTreeMap<Name, Name> {
+        pairs
+            .iter()
+            .map(|(old, new)| (Name::new(old).unwrap(), Name::new(new).unwrap()))
+            .collect()
+    }
+
+    #[test]
+    fn rename_glyphs_in_features_skips_keywords_and_tags() {
+        let features = "\
+languagesystem DFLT dflt;
+feature liga {
+    script latn;
+    language DEU;
+    lookup pos {
+        lookupflag RightToLeft MarkAttachmentType [acutecomb gravecomb];
+        sub \\sub \\pos by dflt; # sub pos
+    } pos;
+    pos A <anchor 10 -20> -50 liga;
+} liga;
+";
+        let renames = renames(&[
+            ("A", "A.alt"),
+            ("acutecomb", "acutecomb.case"),
+            ("RightToLeft", "RightToLeft.alt"),
+            ("DEU", "DEU.alt"),
+            ("DFLT", "DFLT.alt"),
+            ("dflt", "dflt.alt"),
+            ("latn", "latn.alt"),
+            ("liga", "liga.alt"),
+            ("pos", "pos.alt"),
+            ("sub", "sub.alt"),
+        ]);
+        let renamed = rename_glyphs_in_features(features, &renames, |_| true);
+        assert_eq!(
+            renamed,
+            "\
+languagesystem DFLT dflt;
+feature liga {
+    script latn;
+    language DEU;
+    lookup pos {
+        lookupflag RightToLeft MarkAttachmentType [acutecomb.case gravecomb];
+        sub \\sub.alt \\pos.alt by dflt.alt; # sub pos
+    } pos;
+    pos A.alt <anchor 10 -20> -50 liga.alt;
+} liga;
+"
+        );
+    }
+
+    #[test]
+    fn rename_glyphs_in_features_splits_ranges() {
+        let glyphs = ["a", "b", "c", "d", "a-cy", "A", "C", "A.sc", "C.sc"];
+        let is_glyph = |name: &str| glyphs.contains(&name);
+        let features = "\
+@LC = [a-c a-cy b - d];
+sub [A-C] by [A.sc-C.sc];
+sub a-cy by a;
+";
+        let renames = renames(&[("a", "a.alt"), ("A", "A.ss01"), ("C.sc", "C.smcp")]);
+        let renamed = rename_glyphs_in_features(features, &renames, is_glyph);
+        assert_eq!(
+            renamed,
+            "\
+@LC = [a.alt-c a-cy b - d];
+sub [A.ss01-C] by [A.sc-C.smcp];
+sub a-cy by a.alt;
+"
+        );
+
+        // Glyph names with hyphens are split where both ends are glyphs.
+        let names = feature_glyph_names("[a-cy-d]", is_glyph);
+        assert_eq!(names, vec![(1, "a-cy"), (6, "d")]);
+    }
+
+    #[test]
+    fn load_rename_mapping_checks() {
+        let tempdir = tempfile::tempdir().unwrap();
+        let path = tempdir.path().join("renames.csv");
+
+        std::fs::write(&path, "old,new\nA,A.ss01\nB,B.ss01\n").unwrap();
+        assert_eq!(
+            load_rename_mapping(&path).unwrap(),
+            renames(&[("A", "A.ss01"), ("B", "B.ss01")])
+        );
+
+        std::fs::write(&path, "new,old\nA,A.ss01\n").unwrap();
+        assert!(matches!(
+            load_rename_mapping(&path),
+            Err(LoadRenameMappingError::InvalidHeaders(headers)) if headers == "new,old"
+        ));
+
+        std::fs::write(&path, "old,new\nA,A.ss01\nA,A.ss02\n").unwrap();
+        assert!(matches!(
+            load_rename_mapping(&path),
+            Err(LoadRenameMappingError::DuplicateOldName(name)) if name == "A"
+        ));
+
+        std::fs::write(&path, "old,new\nA,\n").unwrap();
+        assert!(matches!(
+            load_rename_mapping(&path),
+            Err(LoadRenameMappingError::Csv(_))
+        ));
+
+        std::fs::write(&path, "old,new\nA,A.ss01,extra\n").unwrap();
+        assert!(matches!(
+            load_rename_mapping(&path),
+            Err(LoadRenameMappingError::Csv(_))
+        ));
+    }
+
+    #[test]
+    fn source_mapping() {
+        let tempdir = tempfile::tempdir().unwrap();
+        let path = tempdir.path().join("mapping.csv");
+        let light_path = tempdir.path().join("Light.ufo");
+        let bold_path = tempdir.path().join("Bold.ufo");
+        let light = norad::Font::load("testdata/MutatorSansLightCondensed.ufo").unwrap();
+        let bold = norad::Font::load("testdata/MutatorSansBoldCondensed.ufo").unwrap();
+        light.save(&light_path).unwrap();
+        bold.save(&bold_path).unwrap();
+
+        std::fs::write(
+            &path,
+            "ufo,family_name,style_name,source_name\nLight.ufo,,,Thin\n,MutatorMathTest,BoldCondensed,Heavy\n",
+        )
+        .unwrap();
+        let mapping = load_source_mapping(&path).unwrap();
+        assert_eq!(
+            map_source_name(&mapping, &light_path, &light),
+            Some(Name::new("Thin").unwrap())
+        );
+        assert_eq!(
+            map_source_name(&mapping, &bold_path, &bold),
+            Some(Name::new("Heavy").unwrap())
+        );
+
+        // Unmapped UFOs are left to the other ways of naming sources.
+        let wide = norad::Font::load("testdata/MutatorSansLightWide.ufo").unwrap();
+        assert_eq!(
+            map_source_name(
+                &mapping,
+                Path::new("testdata/MutatorSansLightWide.ufo"),
+                &wide
+            ),
+            None
+        );
+
+        // A family and style name pair can match more than one UFO, which the
+        // import then rejects as they share a source name.
+        let bold_copy_path = tempdir.path().join("BoldCopy.ufo");
+        bold.save(&bold_copy_path).unwrap();
+        assert_eq!(
+            map_source_name(&mapping, &bold_copy_path, &bold),
+            Some(Name::new("Heavy").unwrap())
+        );
+
+        std::fs::write(
+            &path,
+            "ufo,family_name,style_name,source_name\nLight.ufo,,,Thin\nBold.ufo,,,Thin\n",
+        )
+        .unwrap();
+        assert!(matches!(
+            load_source_mapping(&path),
+            Err(LoadSourceMappingError::DuplicateSourceName(name)) if name == "Thin"
+        ));
+
+        std::fs::write(
+            &path,
+            "ufo,family_name,style_name,source_name\nLight.ufo,,,Thin\nLight.ufo,,,Light\n",
+        )
+        .unwrap();
+        assert!(matches!(
+            load_source_mapping(&path),
+            Err(LoadSourceMappingError::DuplicateEntry(_))
+        ));
+
+        std::fs::write(
+            &path,
+            "ufo,family_name,style_name,source_name\n,A,Bold,Bold\n,A,Bold,Heavy\n",
+        )
+        .unwrap();
+        assert!(matches!(
+            load_source_mapping(&path),
+            Err(LoadSourceMappingError::DuplicateEntry(entry)) if entry == "A Bold"
+        ));
+
+        std::fs::write(
+            &path,
+            "ufo,family_name,style_name,source_name\nLight.ufo,,,\n",
+        )
+        .unwrap();
+        assert!(matches!(
+            load_source_mapping(&path),
+            Err(LoadSourceMappingError::Csv(_))
+        ));
+    }
+
+    #[test]
+    fn merge_glyph_order_places_imported_glyphs() {
+        let names = |names: &[&str]| -> Vec<Name> {
+            names.iter().map(|name| Name::new(name).unwrap()).collect()
+        };
+        let imported = |names: &[&str]| -> HashSet<Name> {
+            names.iter().map(|name| Name::new(name).unwrap()).collect()
+        };
+
+        assert_eq!(
+            merge_glyph_order(&[], &names(&["A", "B", "C"]), &imported(&["C", "A"])),
+            names(&["A", "C"])
+        );
+        // Imported glyphs go after the stored glyph before them in the UFO.
+        assert_eq!(
+            merge_glyph_order(
+                &names(&["A", "C", "x"]),
+                &names(&["a", "A", "B", "C", "D", "x"]),
+                &imported(&["a", "B", "D"])
+            ),
+            names(&["a", "A", "B", "C", "D", "x"])
+        );
+        // Reimported glyphs move, glyphs missing from the UFO order stay.
+        assert_eq!(
+            merge_glyph_order(
+                &names(&["A", "B", "C", "D"]),
+                &names(&["C", "B", "A", "B"]),
+                &imported(&["B", "D"])
+            ),
+            names(&["A", "C", "B", "D"])
+        );
+    }
+
+    #[test]
+    fn numbered_file_names() {
+        assert_eq!(
+            numbered_file_name(Path::new("image.png"), 2),
+            PathBuf::from("image.2.png")
+        );
+        assert_eq!(
+            numbered_file_name(Path::new("image"), 3),
+            PathBuf::from("image.3")
+        );
+    }
+
+    #[test]
+    fn insert_by_content_collision() {
+        let hash = content_hash(b"a");
+        // Pretend that other data has the same hash.
+        let mut store = BTreeMap::from([(hash.clone(), b"b".to_vec())]);
+
+        let key = insert_by_content(&mut store, b"a");
+        assert_eq!(key, format!("{hash}-2"));
+        assert_eq!(store[&hash], b"b".to_vec());
+        assert_eq!(store[&key], b"a".to_vec());
+
+        // Identical data is found under its key.
+        assert_eq!(insert_by_content(&mut store, b"a"), key);
+        assert_eq!(insert_by_content(&mut store, b"b"), hash);
+        assert_eq!(store.len(), 2);
+    }
+}